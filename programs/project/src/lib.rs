@@ -1,15 +1,34 @@
+// Every instruction returns the anchor error, which is larger than clippy would like
+#![allow(clippy::result_large_err)]
+
 use std::vec;
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
 use anchor_spl::token::{Mint, Token, TokenAccount, Transfer};
 use general::program::General;
 use general::{self, GeneralParameter};
 
 declare_id!("45GpJwQe42EXn8EQoyBnp5dU51h2BWrkv8ASmGKERpKD");
 
-const PROJECT_SEED: &'static [u8] = b"project";
-const POOL_SEED: &'static [u8] = b"pool";
-const GENERAL_SEED: &'static [u8] = b"general1";
+const PROJECT_SEED: &[u8] = b"project";
+const POOL_SEED: &[u8] = b"pool";
+const GENERAL_SEED: &[u8] = b"general1";
+const PROPOSAL_SEED: &[u8] = b"proposal";
+const ROTATION_SEED: &[u8] = b"rotation";
+const RECOVERY_SEED: &[u8] = b"recovery";
+const ESCROW_SEED: &[u8] = b"escrow";
+const ESCROW_VAULT_SEED: &[u8] = b"escrow_vault";
+const VOTE_SEED: &[u8] = b"vote";
+
+const PERMISSION_PROPOSE: u8 = 1 << 0;
+const PERMISSION_VOTE: u8 = 1 << 1;
+const PERMISSION_EXECUTE: u8 = 1 << 2;
+const PERMISSION_CANCEL: u8 = 1 << 3;
+const PERMISSION_VETO: u8 = 1 << 4;
+const PERMISSION_ALL: u8 =
+    PERMISSION_PROPOSE | PERMISSION_VOTE | PERMISSION_EXECUTE | PERMISSION_CANCEL | PERMISSION_VETO;
 
 #[program]
 pub mod project {
@@ -22,19 +41,33 @@ pub mod project {
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
         parameters.authority = ctx.accounts.admin.key();
+        parameters.token_mint = ctx.accounts.token_mint.key();
         parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
         // TODO: Check if percent transfer is above the minimum from the general program
         parameters.percent_transfer = percent_transfer;
         parameters.threshold = 1;
         parameters.time_limit = 600;
+        parameters.execution_delay = 0;
+        parameters.closed = false;
+        parameters.kind_thresholds = Vec::new();
+        parameters.threshold_policy = None;
+        parameters.amount_tiers = Vec::new();
+        parameters.config_generation = 0;
+        parameters.delegations = Vec::new();
+        parameters.guardians = Vec::new();
+        parameters.guardian_threshold = 0;
+        parameters.recovery_delay = 0;
+        parameters.governance_mint = None;
+        parameters.token_threshold = 0;
+        parameters.max_members = DEFAULT_MAX_MEMBERS;
+        parameters.proposal_count = 0;
 
         let sig = Signature {
             key: ctx.accounts.authority.key(),
-            add: false,
-            delete: false,
-            change_threshold: false,
-            change_time_limit: false,
-            transfer_amount: false,
+            permissions: PERMISSION_ALL,
+            weight: 1,
+            previous_key: Pubkey::default(),
+            is_project: false,
         };
         parameters.signatories.push(sig);
 
@@ -51,166 +84,237 @@ pub mod project {
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
+        // The initial signatories can only be added once, before anything else changed
+        if parameters.config_generation != 0 {
+            return Err(error!(ErrorCode::SignatoriesAlreadyAdded));
+        }
+
         msg!(&signatures[0].to_string());
         msg!(&signatures[1].to_string());
         // msg!(&signatures.to_string());
 
+        parameters.validate_new_signatories(parameters.key(), &signatures)?;
+        check_not_projects(ctx.remaining_accounts, ctx.program_id, &signatures)?;
+
         parameters.threshold = threshold;
         parameters.time_limit = time_limit;
 
-        for i in 0..signatures.len() {
+        for key in signatures {
             let sig = Signature {
-                key: signatures[i],
-                add: false,
-                delete: false,
-                change_threshold: false,
-                change_time_limit: false,
-                transfer_amount: false,
+                key,
+                permissions: PERMISSION_ALL,
+                weight: 1,
+                previous_key: Pubkey::default(),
+                is_project: false,
             };
             parameters.signatories.push(sig);
         }
+        if threshold > parameters.total_weight() {
+            return Err(error!(ErrorCode::ThresholdIsMore));
+        }
+        parameters.config_generation += 1;
         parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
+
+        resize_project(
+            parameters,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
         Ok(())
     }
 
     pub fn add_new_signatory_proposal(
-        ctx: Context<Proposal>,
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
         _project_id: String,
         signatory: Vec<Pubkey>,
+        weights: Vec<u32>,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
-        let mut index = usize::MAX;
+        parameters.validate_new_signatories(parameters.key(), &signatory)?;
 
-        for i in 0..signatory.len() {
-            index = parameters.get_index(signatory[i]);
-            if index != usize::MAX {
-                return Err(error!(ErrorCode::SignatoryAlreadyExists));
-            }
+        // Without weights every new member gets a weight of 1
+        if (!weights.is_empty() && weights.len() != signatory.len())
+            || weights.iter().any(|weight| *weight == 0 || *weight > MAX_WEIGHT)
+        {
+            return Err(error!(ErrorCode::InvalidWeight));
         }
 
-        if parameters.add.status == true {
-            let current_timestamp = Clock::get().unwrap().unix_timestamp;
-            if (current_timestamp - parameters.add.timestamp) > parameters.time_limit.into() {
-                parameters.create_add(signatory);
-            } else {
-                return Err(error!(ErrorCode::ProposalInProgress));
-            }
-        } else {
-            parameters.create_add(signatory);
-        }
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::AddSignatory {
+                new_signatory: signatory,
+                weights,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
+        Ok(())
+    }
+
+    pub fn add_project_signatory_proposal(
+        ctx: Context<CreateProjectSignatoryProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let child = &ctx.accounts.child_project;
+
+        parameters.validate_child_project(parameters.key(), child.key(), child)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::AddProjectSignatory {
+                project: child.key(),
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
     pub fn remove_signatory_proposal(
-        ctx: Context<Proposal>,
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
         _project_id: String,
         signatory: Vec<Pubkey>,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
-        let mut index = usize::MAX;
+        parameters.validate_old_signatories(&signatory)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::DeleteSignatory {
+                old_signatory: signatory,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
 
-        if parameters.signatories.len() == 1 {
-            return Err(error!(ErrorCode::CannotRemoveSignatory));
-        }
+        Ok(())
+    }
 
-        if parameters.delete.status == true {
-            let current_timestamp = Clock::get().unwrap().unix_timestamp;
-            if (current_timestamp - parameters.delete.timestamp) > parameters.time_limit.into() {
-                for i in 0..signatory.len() {
-                    index = parameters.get_index(signatory[i]);
-                    if index == usize::MAX {
-                        return Err(error!(ErrorCode::SignatoryNotFound));
-                    }
-                }
-                parameters.create_delete(signatory);
-            } else {
-                return Err(error!(ErrorCode::ProposalInProgress));
-            }
-        } else {
-            for i in 0..signatory.len() {
-                index = parameters.get_index(signatory[i]);
-                if index == usize::MAX {
-                    return Err(error!(ErrorCode::SignatoryNotFound));
-                }
-            }
-            parameters.create_delete(signatory);
+    pub fn replace_signatory_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        old_signatory: Pubkey,
+        new_signatory: Pubkey,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        if parameters.get_index(old_signatory) == usize::MAX {
+            return Err(error!(ErrorCode::SignatoryNotFound));
+        }
+        if parameters.get_index(new_signatory) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
         }
+        if new_signatory == parameters.key() {
+            return Err(error!(ErrorCode::InvalidChildProject));
+        }
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ReplaceSignatory {
+                old_signatory,
+                new_signatory,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
     pub fn change_threshold_proposal(
-        ctx: Context<Proposal>,
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
         _project_id: String,
         threshold: u32,
-        current_timestamp: u32,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
         let day = 60 * 60 * 24;
-        // let current_timestamp = Clock::get().unwrap().unix_timestamp;
+        let current_timestamp = Clock::get().unwrap().unix_timestamp as i32;
 
-        if threshold > parameters.signatories.len().try_into().unwrap() {
+        if threshold > parameters.total_weight() {
             return Err(error!(ErrorCode::ThresholdIsMore));
         }
 
-        if (current_timestamp as i32 - parameters.last_tx) / day >= 90 {
+        if (current_timestamp - parameters.last_tx) / day >= 90 {
             msg!("reduce the approvals");
 
-            if parameters.approval < parameters.threshold {
-                let mut months =
-                    (current_timestamp as i32 - parameters.last_reduced_threshold) / day;
-                months = months / 30;
+            // one approval less for every 30 days without a transaction, but never below 1
+            let months = if parameters.approval < parameters.threshold {
+                let months = (current_timestamp - parameters.last_reduced_threshold) / day / 30;
                 if months < 1 {
                     return Err(error!(ErrorCode::MinimumTimeNotPassed));
-                } else {
-                    if parameters.approval - (months as u32) > 1 {
-                        parameters.approval -= months as u32;
-                        parameters.last_reduced_threshold = current_timestamp as i32;
-                        parameters.reduce_approval(threshold);
-                    } else {
-                        parameters.approval = 1;
-                        parameters.last_reduced_threshold = current_timestamp as i32;
-                        parameters.reduce_approval(threshold);
-                    }
                 }
+                months
             } else {
-                let mut months = (current_timestamp as i32 - parameters.last_tx) / day;
-                months = ((months - 90) / 30) + 1;
-                if parameters.approval - (months as u32) > 1 {
-                    parameters.approval -= months as u32;
-                    parameters.last_reduced_threshold = current_timestamp as i32;
-                } else {
-                    parameters.approval = 1;
-                    parameters.last_reduced_threshold = current_timestamp as i32;
-                }
-            }
+                ((current_timestamp - parameters.last_tx) / day - 90) / 30 + 1
+            };
+            parameters.approval = parameters.approval.saturating_sub(months as u32).max(1);
+            parameters.last_reduced_threshold = current_timestamp;
         } else {
-            if parameters.change_threshold.status == true {
-                if (current_timestamp as i64 - parameters.change_threshold.timestamp)
-                    > parameters.time_limit.into()
-                {
-                    parameters.create_change(threshold);
-                } else {
-                    return Err(error!(ErrorCode::ProposalInProgress));
-                }
-            } else {
-                parameters.create_change(threshold);
-            }
+            parameters.approval = parameters.kind_threshold(ProposalKind::ChangeThreshold);
         }
 
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeThreshold {
+                new_threshold: threshold,
+            },
+            metadata,
+        )?;
+        // Later proposals cannot change the approvals this one needs
+        ctx.accounts.proposal.required_votes = parameters.approval;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
     pub fn change_time_limit_proposal(
-        ctx: Context<Proposal>,
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
         _project_id: String,
         time_limit: u32,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
@@ -222,340 +326,1373 @@ pub mod project {
             return Err(error!(ErrorCode::TimeoutMore));
         }
 
-        if parameters.change_time_limit.status == true {
-            let current_timestamp = Clock::get().unwrap().unix_timestamp;
-            if (current_timestamp - parameters.change_time_limit.timestamp)
-                > parameters.time_limit.into()
-            {
-                parameters.create_time_limit(time_limit);
-            } else {
-                return Err(error!(ErrorCode::ProposalInProgress));
-            }
-        } else {
-            parameters.create_time_limit(time_limit);
-        }
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeTimeLimit {
+                new_time_limit: time_limit,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
-    pub fn transfer_amount_proposal(
-        ctx: Context<Proposal>,
+    pub fn change_execution_delay_proposal(
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
         _project_id: String,
-        amount: u32,
-        reciever: Pubkey,
+        execution_delay: u32,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
-        if amount > parameters.staked_amount {
-            return Err(error!(ErrorCode::InsufficientBalance));
+        if execution_delay > 60 * 60 * 24 * 30 {
+            return Err(error!(ErrorCode::ExecutionDelayMore));
         }
 
-        if parameters.transfer_amount.status == true {
-            let current_timestamp = Clock::get().unwrap().unix_timestamp;
-            if (current_timestamp - parameters.transfer_amount.timestamp)
-                > parameters.time_limit.into()
-            {
-                parameters.create_transfer_amount(amount, reciever);
-            } else {
-                return Err(error!(ErrorCode::ProposalInProgress));
-            }
-        } else {
-            parameters.create_transfer_amount(amount, reciever);
-        }
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeExecutionDelay {
+                new_delay: execution_delay,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
-    pub fn shutdown_proposal (
-        ctx: Context<Proposal>,
+    pub fn change_kind_thresholds_proposal(
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
-        _project_id: String
+        _project_id: String,
+        thresholds: Vec<KindThreshold>,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
-        if parameters.staked_amount > 0 {
-            return Err(error!(ErrorCode::EmptyFundsBeforeClosing))
+        parameters.validate_kind_thresholds(&thresholds)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeKindThresholds { thresholds },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
+        Ok(())
+    }
+
+    pub fn change_threshold_policy_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        policy: Option<ThresholdPolicy>,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        if let Some(policy) = &policy {
+            policy.validate()?;
         }
-        else {
-            parameters.create_shutdown();
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeThresholdPolicy { policy },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
+        Ok(())
+    }
+
+    pub fn change_amount_tiers_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        tiers: Vec<AmountTier>,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        parameters.validate_amount_tiers(&tiers)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeAmountTiers { tiers },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
+        Ok(())
+    }
+
+    pub fn change_permissions_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        signatory: Pubkey,
+        permissions: u8,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        let index = parameters.get_index(signatory);
+        if index == usize::MAX {
+            return Err(error!(ErrorCode::SignatoryNotFound));
         }
+        parameters.validate_permissions(index, permissions)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangePermissions {
+                signatory,
+                permissions,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
-    pub fn sign_proposal(
-        ctx: Context<SignProposal>,
+    pub fn change_weight_proposal(
+        ctx: Context<CreateProposal>,
         _base_bump: u8,
         _project_id: String,
-        key: String,
+        signatory: Pubkey,
+        weight: u32,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
-        let matching_key = &key[..];
         let parameters = &mut ctx.accounts.base_account;
-        let final_index = parameters.get_index(ctx.accounts.authority.key());
 
-        if final_index == usize::MAX {
-            return Err(error!(ErrorCode::InvalidSigner));
+        let index = parameters.get_index(signatory);
+        if index == usize::MAX {
+            return Err(error!(ErrorCode::SignatoryNotFound));
         }
+        parameters.validate_weight(index, weight)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeWeight { signatory, weight },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
 
-        match matching_key {
-            "add" => {
-                if parameters.add.status == true {
-                    if parameters.signatories[final_index].add == false {
-                        parameters.signatories[final_index].add = true;
-                        parameters.add.votes += 1;
-
-                        if parameters.add.votes >= parameters.threshold {
-                            for i in 0..parameters.add.new_signatory.len() {
-                                let sig = Signature {
-                                    key: parameters.add.new_signatory[i],
-                                    add: false,
-                                    delete: false,
-                                    change_threshold: false,
-                                    change_time_limit: false,
-                                    transfer_amount: false,
-                                };
-                                parameters.signatories.push(sig);
-                            }
-                            parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
-                            parameters.reset_add();
-                        }
-                    } else {
-                        return Err(error!(ErrorCode::RepeatedSignature));
-                    }
-                } else {
-                    return Err(error!(ErrorCode::NoProposalCreated));
-                }
-            }
-            "delete" => {
-                if parameters.delete.status == true {
-                    if parameters.signatories[final_index].delete == false {
-                        parameters.signatories[final_index].delete = true;
-                        parameters.delete.votes += 1;
-
-                        let mut index = usize::MAX;
-                        let mut allIndex = Vec::new();
-
-                        for i in 0..parameters.delete.old_signatory.len() {
-                            index = parameters.get_index(parameters.delete.old_signatory[i]);
-                            if index == usize::MAX {
-                                return Err(error!(ErrorCode::SignatoryNotFound));
-                            } else {
-                                allIndex.push(index);
-                            }
-                        }
+        Ok(())
+    }
 
-                        allIndex.sort();
-
-                        if parameters.delete.votes >= parameters.threshold {
-                            for i in 0..allIndex.len() {
-                                parameters.signatories.remove(allIndex[i] - i);
-                            }
-                            if parameters.threshold
-                                > parameters.signatories.len().try_into().unwrap()
-                            {
-                                parameters.threshold =
-                                    parameters.signatories.len().try_into().unwrap();
-                            }
-                            parameters.reset_delete();
-                            parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
-                        }
-                    } else {
-                        return Err(error!(ErrorCode::RepeatedSignature));
-                    }
-                } else {
-                    return Err(error!(ErrorCode::NoProposalCreated));
-                }
+    pub fn change_guardians_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        guardians: Vec<Pubkey>,
+        threshold: u32,
+        delay: u32,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        // An empty set turns recovery off, otherwise the guardians need a reachable threshold
+        // and have to wait at least the minimum delay
+        if guardians.len() > MAX_GUARDIANS {
+            return Err(error!(ErrorCode::InvalidGuardians));
+        }
+        for (i, guardian) in guardians.iter().enumerate() {
+            if guardians[..i].contains(guardian) {
+                return Err(error!(ErrorCode::InvalidGuardians));
             }
-            "change threshold" => {
-                if parameters.change_threshold.status == true {
-                    if parameters.signatories[final_index].change_threshold == false {
-                        parameters.signatories[final_index].change_threshold = true;
-                        parameters.change_threshold.votes += 1;
-
-                        if parameters.change_threshold.votes >= parameters.approval {
-                            parameters.threshold = parameters.change_threshold.new_threshold;
-                            parameters.approval = parameters.change_threshold.new_threshold;
-                            parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
-                            parameters.reset_change();
-                        }
-                    } else {
-                        return Err(error!(ErrorCode::RepeatedSignature));
-                    }
-                } else {
-                    return Err(error!(ErrorCode::NoProposalCreated));
-                }
+        }
+        if guardians.is_empty() {
+            if threshold != 0 {
+                return Err(error!(ErrorCode::InvalidGuardians));
             }
-            "change time limit" => {
-                if parameters.change_time_limit.status == true {
-                    if parameters.signatories[final_index].change_time_limit == false {
-                        parameters.signatories[final_index].change_time_limit = true;
-                        parameters.change_time_limit.votes += 1;
+        } else if threshold == 0
+            || threshold > guardians.len().try_into().unwrap()
+            || delay < MIN_RECOVERY_DELAY
+        {
+            return Err(error!(ErrorCode::InvalidGuardians));
+        }
 
-                        if parameters.change_time_limit.votes >= parameters.threshold {
-                            parameters.time_limit = parameters.change_time_limit.new_time_limit;
-                            parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
-                            parameters.reset_time_limit();
-                        }
-                    } else {
-                        return Err(error!(ErrorCode::RepeatedSignature));
-                    }
-                } else {
-                    return Err(error!(ErrorCode::NoProposalCreated));
-                }
-            }
-            _ => msg!("Wrong proposal"),
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeGuardians {
+                guardians,
+                threshold,
+                delay,
+            },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
+        Ok(())
+    }
+
+    pub fn change_governance_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        mint: Option<Pubkey>,
+        threshold: u64,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        // Without a mint the project goes back to its signatories and the token threshold is unused
+        if (mint.is_some() && threshold == 0) || (mint.is_none() && threshold != 0) {
+            return Err(error!(ErrorCode::InvalidGovernance));
         }
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeGovernance { mint, threshold },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
-    pub fn deposit_funds(
-        ctx: Context<Deposit>,
-        project_id: String,
-        project_bump: u8,
-        _pool_bump: u8,
-        _general_bump: u8,
-        amount: u32,
+    pub fn change_max_members_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        max_members: u32,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
-        let general_parameters = &mut ctx.accounts.general_account;
 
-        if general_parameters.token_mint == ctx.accounts.token_mint.key() {
-            msg!("Mint is matching");
+        parameters.validate_max_members(max_members)?;
 
-            let bump_vector = project_bump.to_le_bytes();
-            let inner = vec![
-                PROJECT_SEED,
-                project_id.as_bytes()[..18].as_ref(),
-                project_id.as_bytes()[18..].as_ref(),
-                bump_vector.as_ref(),
-            ];
-            let outer = vec![inner.as_slice()];
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::ChangeMaxMembers { max_members },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
 
-            let amount_to_transfer =
-                (amount * (100 - (parameters.percent_transfer as u32)) / 100) as u64;
-            let amount_to_deduct = (amount - (amount_to_transfer as u32)) as u64;
+        Ok(())
+    }
 
-            // Below is the actual instruction that we are going to send to the Token program.
-            let transfer_instruction = Transfer {
-                from: ctx.accounts.wallet_to_withdraw_from.to_account_info(),
-                to: ctx.accounts.project_pool_account.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            };
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_instruction,
-                outer.as_slice(), //signer PDA
-            );
+    pub fn transfer_amount_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        amount: u32,
+        reciever: Pubkey,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
 
-            let another_transfer_instruction = Transfer {
-                from: ctx.accounts.wallet_to_withdraw_from.to_account_info(),
-                to: ctx.accounts.admin_token_wallet.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            };
+        parameters.validate_transfer_amount(amount as u64)?;
 
-            let another_cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                another_transfer_instruction,
-                outer.as_slice(), //signer PDA
-            );
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::TransferAmount { amount, reciever },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
 
-            msg!("{} {}", amount_to_transfer, amount_to_deduct);
+        Ok(())
+    }
 
-            // The `?` at the end will cause the function to return early in case of an error.
-            // This pattern is common in Rust.
-            anchor_spl::token::transfer(cpi_ctx, amount_to_transfer)?;
-            anchor_spl::token::transfer(another_cpi_ctx, amount_to_deduct)?;
+    pub fn shutdown_proposal(
+        ctx: Context<CreateProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
 
-            parameters.staked_amount += amount_to_transfer as u32;
+        if parameters.staked_amount > 0 {
+            return Err(error!(ErrorCode::EmptyFundsBeforeClosing));
         }
 
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(origin, ProposalAction::Shutdown, metadata)?;
+        parameters.proposal_count += 1;
+
         Ok(())
     }
 
-    pub fn sign_transfer(
-        ctx: Context<SignTransfer>,
-        _general_bump: u8,
-        project_bump: u8,
-        _pool_bump: u8,
-        project_id: String,
+    pub fn instruction_proposal(
+        ctx: Context<CreateInstructionProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        instruction: ProposalInstruction,
+        metadata: ProposalMetadata,
     ) -> Result<()> {
         let parameters = &mut ctx.accounts.base_account;
 
-        let final_index = parameters.get_index(ctx.accounts.authority.key());
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(
+            origin,
+            ProposalAction::Instruction { instruction },
+            metadata,
+        )?;
+        parameters.proposal_count += 1;
 
-        if final_index == usize::MAX {
-            return Err(error!(ErrorCode::InvalidSigner));
+        Ok(())
+    }
+
+    pub fn batch_proposal(
+        ctx: Context<CreateBatchProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        actions: Vec<BatchAction>,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        parameters.validate_batch(parameters.key(), &actions)?;
+
+        let origin = ProposalOrigin::new(
+            parameters,
+            ctx.accounts.authority.key(),
+            *ctx.bumps.get("proposal").unwrap(),
+        );
+        ctx.accounts.proposal.create(origin, ProposalAction::Batch { actions }, metadata)?;
+        // Batches stay in draft so that more actions can be added before voting starts
+        ctx.accounts.proposal.state = ProposalState::Draft;
+        parameters.proposal_count += 1;
+
+        Ok(())
+    }
+
+    pub fn add_batch_action(
+        ctx: Context<AddBatchAction>,
+        _base_bump: u8,
+        _project_id: String,
+        action: BatchAction,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+
+        if ctx.accounts.proposal.state != ProposalState::Draft {
+            return Err(error!(ErrorCode::ProposalNotDraft));
         }
 
-        if parameters.transfer_amount.status == true {
-            if parameters.signatories[final_index].transfer_amount == false {
-                parameters.signatories[final_index].transfer_amount = true;
-                parameters.transfer_amount.votes += 1;
-
-                if parameters.transfer_amount.votes >= parameters.threshold {
-                    if parameters.transfer_amount.reciever
-                        != ctx.accounts.wallet_to_withdraw_from.key()
-                    {
-                        return Err(error!(ErrorCode::InvalidReciever));
-                    } else {
-                        if !parameters.shutdown && parameters.threshold == 1 {
-                            return Err(error!(ErrorCode::CannotTransferDueToLowThreshold));
-                        } else {
-                            msg!("transfering the amount to the reciever");
-
-                            let bump_vector = project_bump.to_le_bytes();
-                            let inner = vec![
-                                PROJECT_SEED,
-                                project_id.as_bytes()[..18].as_ref(),
-                                project_id.as_bytes()[18..].as_ref(),
-                                bump_vector.as_ref(),
-                            ];
-                            let outer = vec![inner.as_slice()];
-
-                            // Below is the actual instruction that we are going to send to the Token program.
-                            let transfer_instruction = Transfer {
-                                from: ctx.accounts.project_pool_account.to_account_info(),
-                                to: ctx.accounts.wallet_to_withdraw_from.to_account_info(),
-                                authority: parameters.to_account_info(),
-                            };
-                            let cpi_ctx = CpiContext::new_with_signer(
-                                ctx.accounts.token_program.to_account_info(),
-                                transfer_instruction,
-                                outer.as_slice(), //signer PDA
-                            );
-
-                            let amount_in_64 = parameters.transfer_amount.amount as u64;
-
-                            // The `?` at the end will cause the function to return early in case of an error.
-                            // This pattern is common in Rust.
-                            anchor_spl::token::transfer(cpi_ctx, amount_in_64)?;
-                            parameters.staked_amount -= parameters.transfer_amount.amount;
-                        }
-                    }
+        // The new action is checked together with the ones already in the batch
+        let mut actions = match &ctx.accounts.proposal.action {
+            ProposalAction::Batch { actions } => actions.clone(),
+            _ => return Err(error!(ErrorCode::WrongProposalKind)),
+        };
+        actions.push(action.clone());
+        parameters.validate_batch(parameters.key(), &actions)?;
+
+        // Grow the proposal account to fit the new action, the proposer pays for the extra rent
+        let proposal_info = ctx.accounts.proposal.to_account_info();
+        let new_len = proposal_info.data_len() + action.size();
+        let minimum_balance = Rent::get()?.minimum_balance(new_len);
+        if minimum_balance > proposal_info.lamports() {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: proposal_info.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(
+                cpi_ctx,
+                minimum_balance - proposal_info.lamports(),
+            )?;
+        }
+        proposal_info.realloc(new_len, false)?;
 
-                    parameters.reset_transfer_amount();
-                }
-            } else {
-                return Err(error!(ErrorCode::RepeatedSignature));
-            }
-        } else {
-            return Err(error!(ErrorCode::NoProposalCreated));
+        match &mut ctx.accounts.proposal.action {
+            ProposalAction::Batch { actions } => actions.push(action),
+            _ => return Err(error!(ErrorCode::WrongProposalKind)),
         }
 
         Ok(())
     }
 
-    pub fn fall_back(
-        ctx: Context<FallBack>,
+    pub fn activate_proposal(
+        ctx: Context<ActivateProposal>,
         _base_bump: u8,
         _project_id: String,
-        current_time: i32,
     ) -> Result<()> {
-        let parameters = &mut ctx.accounts.base_account;
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
 
-        let day: i32 = 60 * 60 * 24; // 1 day
+        if proposal.state != ProposalState::Draft {
+            return Err(error!(ErrorCode::ProposalNotDraft));
+        }
 
-        if !parameters.shutdown {
-            if (current_time - parameters.last_tx) / day >= 90 {
-                parameters.shutdown = true;
+        // The voting period starts when the draft is activated
+        proposal.state = ProposalState::Active;
+        proposal.timestamp = Clock::get().unwrap().unix_timestamp;
+        proposal.expires_at = proposal.timestamp + parameters.time_limit as i64;
+        proposal.config_generation = parameters.config_generation;
+        proposal.voting_weight = u64::from(parameters.total_weight());
+
+        Ok(())
+    }
+
+    pub fn sign_proposal(
+        ctx: Context<SignProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        kind: ProposalKind,
+        slot: u32,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+
+        cast_vote(parameters, proposal, ctx.accounts.authority.key(), slot, kind)
+    }
+
+    pub fn sign_proposal_as_delegate(
+        ctx: Context<SignProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        kind: ProposalKind,
+        delegator: Pubkey,
+        slot: u32,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+
+        // The slot is the one of the delegator, whose weight the delegate votes with
+        parameters.vote_weight(delegator, slot)?;
+
+        if !parameters.is_delegate(delegator, ctx.accounts.authority.key(), kind) {
+            return Err(error!(ErrorCode::NotDelegate));
+        }
+
+        msg!("{} signs for {}", ctx.accounts.authority.key(), delegator);
+        cast_vote(parameters, proposal, delegator, slot, kind)
+    }
+
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>,
+        base_bump: u8,
+        project_id: String,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+
+        // Token holders approve proposals themselves, anyone can execute them afterwards
+        if parameters.governance_mint.is_none() {
+            parameters.check_permission(ctx.accounts.authority.key(), PERMISSION_EXECUTE)?;
+        }
+
+        if proposal.state != ProposalState::Approved {
+            return Err(error!(ErrorCode::ProposalNotApproved));
+        }
+
+        // The members or thresholds changed since the proposal was voted on
+        if proposal.is_stale(parameters.config_generation) {
+            return Err(error!(ErrorCode::StaleProposal));
+        }
+
+        // The threshold could have been raised after the proposal was approved
+        if proposal.approval_weight < parameters.required_weight(proposal) {
+            return Err(error!(ErrorCode::ProposalNotApproved));
+        }
+
+        // The delay of an amount tier could have been raised after the proposal was approved
+        let current_timestamp = Clock::get().unwrap().unix_timestamp;
+        if current_timestamp < proposal.executable_at
+            || current_timestamp
+                < proposal.approved_at + parameters.required_delay(&proposal.action) as i64
+        {
+            return Err(error!(ErrorCode::TimelockNotPassed));
+        }
+
+        let bump_vector = base_bump.to_le_bytes();
+        let inner = vec![
+            PROJECT_SEED,
+            project_id.as_bytes()[..18].as_ref(),
+            project_id.as_bytes()[18..].as_ref(),
+            bump_vector.as_ref(),
+        ];
+        let outer = vec![inner.as_slice()];
+
+        let (pool, _) = Pubkey::find_program_address(
+            &[
+                POOL_SEED,
+                project_id.as_bytes()[..18].as_ref(),
+                project_id.as_bytes()[18..].as_ref(),
+            ],
+            ctx.program_id,
+        );
+
+        // Accounts needed by transfers and instructions are passed as remaining accounts
+        match proposal.action.clone() {
+            ProposalAction::AddSignatory {
+                new_signatory,
+                weights,
+            } => {
+                check_not_projects(ctx.remaining_accounts, ctx.program_id, &new_signatory)?;
+                parameters.add_signatories(&new_signatory, &weights)?;
+            }
+            ProposalAction::AddProjectSignatory { project } => {
+                // the child may have taken other projects as members since the proposal was made
+                let child_info = find_account(ctx.remaining_accounts, &project)?;
+                if child_info.owner != ctx.program_id {
+                    return Err(error!(ErrorCode::InvalidChildProject));
+                }
+                let child = ProjectParameter::try_deserialize(&mut &child_info.data.borrow()[..])?;
+                parameters.validate_child_project(parameters.key(), project, &child)?;
+                parameters.add_project_signatory(project)?;
+            }
+            ProposalAction::DeleteSignatory { old_signatory } => {
+                parameters.remove_signatories(&old_signatory)?;
+            }
+            ProposalAction::ReplaceSignatory {
+                old_signatory,
+                new_signatory,
+            } => {
+                check_not_projects(ctx.remaining_accounts, ctx.program_id, &[new_signatory])?;
+                parameters.replace_signatory(old_signatory, new_signatory)?;
+            }
+            ProposalAction::ChangeThreshold { new_threshold } => {
+                if new_threshold > parameters.total_weight() {
+                    return Err(error!(ErrorCode::ThresholdIsMore));
+                }
+                parameters.threshold = new_threshold;
+                parameters.approval = new_threshold;
+                parameters.last_reduced_threshold = 0;
+                parameters.shutdown = false;
+                // a fixed threshold replaces the percentage mode
+                parameters.threshold_policy = None;
+            }
+            ProposalAction::ChangeThresholdPolicy { policy } => {
+                parameters.threshold_policy = policy;
+                parameters.apply_threshold_policy();
+            }
+            ProposalAction::ChangeAmountTiers { tiers } => {
+                parameters.validate_amount_tiers(&tiers)?;
+                parameters.amount_tiers = tiers;
+            }
+            ProposalAction::ChangePermissions {
+                signatory,
+                permissions,
+            } => {
+                let index = parameters.get_index(signatory);
+                if index == usize::MAX {
+                    return Err(error!(ErrorCode::SignatoryNotFound));
+                }
+                // other proposals may have moved the thresholds since this one was created
+                parameters.validate_permissions(index, permissions)?;
+                parameters.signatories[index].permissions = permissions;
+                parameters.apply_threshold_policy();
+            }
+            ProposalAction::ChangeWeight { signatory, weight } => {
+                let index = parameters.get_index(signatory);
+                if index == usize::MAX {
+                    return Err(error!(ErrorCode::SignatoryNotFound));
+                }
+                parameters.validate_weight(index, weight)?;
+                parameters.signatories[index].weight = weight;
+                parameters.apply_threshold_policy();
+            }
+            ProposalAction::ChangeGuardians {
+                guardians,
+                threshold,
+                delay,
+            } => {
+                parameters.guardians = guardians;
+                parameters.guardian_threshold = threshold;
+                parameters.recovery_delay = delay;
+            }
+            ProposalAction::ChangeMaxMembers { max_members } => {
+                parameters.validate_max_members(max_members)?;
+                parameters.max_members = max_members;
+            }
+            ProposalAction::ChangeGovernance { mint, threshold } => {
+                parameters.governance_mint = mint;
+                parameters.token_threshold = threshold;
+            }
+            ProposalAction::ChangeTimeLimit { new_time_limit } => {
+                parameters.time_limit = new_time_limit;
+            }
+            ProposalAction::ChangeExecutionDelay { new_delay } => {
+                parameters.execution_delay = new_delay;
+            }
+            ProposalAction::ChangeKindThresholds { thresholds } => {
+                parameters.validate_kind_thresholds(&thresholds)?;
+                parameters.set_kind_thresholds(&thresholds);
+            }
+            ProposalAction::TransferAmount { amount, reciever } => {
+                msg!("transfering the amount to the reciever");
+                execute_transfer(
+                    parameters,
+                    ctx.remaining_accounts,
+                    &pool,
+                    &reciever,
+                    outer.as_slice(),
+                    amount,
+                )?;
+            }
+            ProposalAction::Shutdown => {
+                if parameters.staked_amount > 0 {
+                    return Err(error!(ErrorCode::EmptyFundsBeforeClosing));
+                }
+                msg!("closing the project");
+                parameters.closed = true;
+            }
+            ProposalAction::Instruction { instruction } => {
+                msg!("executing the instruction as the project");
+                invoke_instruction(
+                    &instruction,
+                    ctx.remaining_accounts,
+                    parameters.to_account_info(),
+                    outer.as_slice(),
+                )?;
+            }
+            ProposalAction::Batch { actions } => {
+                msg!("executing the batch of {} actions", actions.len());
+
+                // Every action runs in this instruction, if one fails the whole batch is reverted.
+                for action in &actions {
+                    match action {
+                        BatchAction::TransferAmount { amount, reciever } => {
+                            execute_transfer(
+                                parameters,
+                                ctx.remaining_accounts,
+                                &pool,
+                                reciever,
+                                outer.as_slice(),
+                                *amount,
+                            )?;
+                        }
+                        BatchAction::AddSignatory { new_signatory } => {
+                            check_not_projects(
+                                ctx.remaining_accounts,
+                                ctx.program_id,
+                                new_signatory,
+                            )?;
+                            parameters.add_signatories(new_signatory, &[])?;
+                        }
+                        BatchAction::DeleteSignatory { old_signatory } => {
+                            parameters.remove_signatories(old_signatory)?;
+                        }
+                        BatchAction::Instruction { instruction } => {
+                            invoke_instruction(
+                                instruction,
+                                ctx.remaining_accounts,
+                                parameters.to_account_info(),
+                                outer.as_slice(),
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Membership and threshold changes make every other pending proposal stale
+        if proposal.action.changes_config() {
+            parameters.config_generation += 1;
+        }
+
+        parameters.last_tx = Clock::get().unwrap().unix_timestamp as i32;
+        proposal.state = ProposalState::Executed;
+
+        // The executor pays for a larger project or gets the rent back from a smaller one
+        resize_project(
+            parameters,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn reject_proposal(
+        ctx: Context<SignProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        slot: u32,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+
+        if parameters.governance_mint.is_some() {
+            return Err(error!(ErrorCode::TokenVotingEnabled));
+        }
+
+        let weight = parameters.vote_weight(ctx.accounts.authority.key(), slot)?;
+
+        if proposal.state != ProposalState::Active {
+            return Err(error!(ErrorCode::NoProposalCreated));
+        }
+
+        if proposal.is_expired() {
+            return Err(error!(ErrorCode::ProposalExpired));
+        }
+
+        if proposal.is_stale(parameters.config_generation) {
+            return Err(error!(ErrorCode::StaleProposal));
+        }
+
+        proposal.reject(slot as usize, weight)?;
+        reject_if_unreachable(parameters, proposal);
+
+        Ok(())
+    }
+
+    pub fn cancel_proposal(
+        ctx: Context<SignProposal>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+        let key = ctx.accounts.authority.key();
+
+        if proposal.state != ProposalState::Draft
+            && proposal.state != ProposalState::Active
+            && proposal.state != ProposalState::Approved
+        {
+            return Err(error!(ErrorCode::NoProposalCreated));
+        }
+
+        if key == proposal.proposer || parameters.previous_key(key) == proposal.proposer {
+            msg!("the proposal has been withdrawn by the proposer");
+            proposal.state = ProposalState::Cancelled;
+            return Ok(());
+        }
+
+        // Only the proposer can withdraw a proposal the token holders vote on
+        if parameters.governance_mint.is_some() {
+            return Err(error!(ErrorCode::TokenVotingEnabled));
+        }
+
+        parameters.check_permission(key, PERMISSION_CANCEL)?;
+
+        let slot = parameters.get_index(key);
+        if slot_is_set(&proposal.cancellers, slot) {
+            return Err(error!(ErrorCode::RepeatedSignature));
+        }
+        set_slot(&mut proposal.cancellers, slot, true);
+        proposal.cancellations += 1;
+        proposal.cancellation_weight += u64::from(parameters.signatories[slot].weight);
+
+        if proposal.cancellation_weight >= u64::from(parameters.threshold) {
+            msg!("the proposal has been cancelled by the members");
+            proposal.state = ProposalState::Cancelled;
+        }
+
+        Ok(())
+    }
+
+    pub fn revoke_vote(
+        ctx: Context<SignProposal>,
+        _base_bump: u8,
+        _project_id: String,
+        slot: u32,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+
+        if proposal.state != ProposalState::Active && proposal.state != ProposalState::Approved {
+            return Err(error!(ErrorCode::NoProposalCreated));
+        }
+
+        let weight = match parameters.signatories.get(slot as usize) {
+            Some(member) if member.key == ctx.accounts.authority.key() => member.weight,
+            _ => return Err(error!(ErrorCode::NoVoteToRevoke)),
+        };
+        proposal.revoke(slot as usize, u64::from(weight))?;
+
+        if proposal.approval_weight < parameters.required_weight(proposal) {
+            proposal.state = ProposalState::Active;
+        }
+
+        Ok(())
+    }
+
+    pub fn delegate_vote(
+        ctx: Context<ManageDelegation>,
+        _base_bump: u8,
+        _project_id: String,
+        delegate: Pubkey,
+        kind: Option<ProposalKind>,
+        expires_at: i64,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let delegator = ctx.accounts.authority.key();
+
+        parameters.check_permission(delegator, PERMISSION_VOTE)?;
+
+        if delegate == delegator {
+            return Err(error!(ErrorCode::InvalidDelegation));
+        }
+
+        // An expiry of 0 keeps the delegation until it is revoked
+        let now = Clock::get().unwrap().unix_timestamp;
+        if expires_at != 0 && expires_at <= now {
+            return Err(error!(ErrorCode::InvalidDelegation));
+        }
+
+        // A new delegation replaces the one for the same kind and expired ones make room
+        parameters.delegations.retain(|delegation| {
+            delegation.is_active(now)
+                && !(delegation.delegator == delegator && delegation.kind == kind)
+        });
+        let delegations = parameters.delegations.iter();
+        let own = delegations.filter(|delegation| delegation.delegator == delegator).count();
+        if own >= MAX_DELEGATIONS_PER_MEMBER || parameters.delegations.len() >= MAX_DELEGATIONS {
+            return Err(error!(ErrorCode::TooManyDelegations));
+        }
+        parameters.delegations.push(Delegation {
+            delegator,
+            delegate,
+            kind,
+            expires_at,
+        });
+
+        resize_project(
+            parameters,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn revoke_delegation(
+        ctx: Context<ManageDelegation>,
+        _base_bump: u8,
+        _project_id: String,
+        kind: Option<ProposalKind>,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let delegator = ctx.accounts.authority.key();
+
+        let index = parameters
+            .delegations
+            .iter()
+            .position(|delegation| delegation.delegator == delegator && delegation.kind == kind);
+        match index {
+            Some(index) => {
+                parameters.delegations.remove(index);
+            }
+            None => return Err(error!(ErrorCode::DelegationNotFound)),
+        }
+
+        resize_project(
+            parameters,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn request_key_rotation(
+        ctx: Context<RequestKeyRotation>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let old_key = ctx.accounts.authority.key();
+        let new_key = ctx.accounts.new_authority.key();
+
+        let index = parameters.get_index(old_key);
+        if index == usize::MAX {
+            return Err(error!(ErrorCode::InvalidSigner));
+        }
+
+        if parameters.get_index(new_key) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
+        }
+
+        let now = Clock::get().unwrap().unix_timestamp;
+
+        let rotation = &mut ctx.accounts.rotation;
+        rotation.project = parameters.key();
+        rotation.old_key = old_key;
+        rotation.new_key = new_key;
+        rotation.executable_at = now + i64::from(parameters.execution_delay);
+        rotation.bump = *ctx.bumps.get("rotation").unwrap();
+
+        Ok(())
+    }
+
+    pub fn complete_key_rotation(
+        ctx: Context<CompleteKeyRotation>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let rotation = &ctx.accounts.rotation;
+
+        let now = Clock::get().unwrap().unix_timestamp;
+        if now < rotation.executable_at {
+            return Err(error!(ErrorCode::TimelockNotPassed));
+        }
+
+        parameters.rotate_key(rotation.old_key, rotation.new_key)?;
+
+        Ok(())
+    }
+
+    pub fn object_key_rotation(
+        ctx: Context<ObjectKeyRotation>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let key = ctx.accounts.authority.key();
+
+        // The rotating member can also call off their own request
+        if key != ctx.accounts.rotation.old_key {
+            parameters.check_permission(key, PERMISSION_VETO)?;
+        }
+
+        msg!("the key rotation has been objected to");
+        Ok(())
+    }
+
+    pub fn start_recovery(
+        ctx: Context<StartRecovery>,
+        _base_bump: u8,
+        _project_id: String,
+        lost_key: Pubkey,
+        new_key: Pubkey,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let guardian = ctx.accounts.guardian.key();
+
+        if !parameters.guardians.contains(&guardian) {
+            return Err(error!(ErrorCode::NotGuardian));
+        }
+
+        if parameters.get_index(lost_key) == usize::MAX {
+            return Err(error!(ErrorCode::SignatoryNotFound));
+        }
+
+        if parameters.get_index(new_key) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
+        }
+
+        let recovery = &mut ctx.accounts.recovery;
+        recovery.project = parameters.key();
+        recovery.lost_key = lost_key;
+        recovery.new_key = new_key;
+        recovery.initiator = guardian;
+        recovery.approvals = Vec::new();
+        recovery.executable_at = 0;
+        recovery.bump = *ctx.bumps.get("recovery").unwrap();
+        recovery.approve(parameters, guardian)?;
+
+        Ok(())
+    }
+
+    pub fn approve_recovery(
+        ctx: Context<ApproveRecovery>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let guardian = ctx.accounts.guardian.key();
+
+        if !parameters.guardians.contains(&guardian) {
+            return Err(error!(ErrorCode::NotGuardian));
+        }
+
+        ctx.accounts.recovery.approve(parameters, guardian)
+    }
+
+    pub fn complete_recovery(
+        ctx: Context<CompleteRecovery>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let recovery = &ctx.accounts.recovery;
+
+        // The guardian set may have changed or been removed since the approvals were given
+        if recovery.executable_at == 0
+            || parameters.guardian_threshold == 0
+            || recovery.guardian_approvals(parameters) < parameters.guardian_threshold
+        {
+            return Err(error!(ErrorCode::RecoveryNotApproved));
+        }
+
+        let now = Clock::get().unwrap().unix_timestamp;
+        if now < recovery.executable_at {
+            return Err(error!(ErrorCode::TimelockNotPassed));
+        }
+
+        msg!("recovering {} to {}", recovery.lost_key, recovery.new_key);
+        parameters.rotate_key(recovery.lost_key, recovery.new_key)?;
+
+        Ok(())
+    }
+
+    pub fn cancel_recovery(
+        ctx: Context<CancelRecovery>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let key = ctx.accounts.authority.key();
+
+        // An owner who still holds the key can always stop the recovery of their seat
+        if key != ctx.accounts.recovery.lost_key {
+            parameters.check_permission(key, PERMISSION_VETO)?;
+        }
+
+        msg!("the recovery has been cancelled");
+        Ok(())
+    }
+
+    pub fn create_voter_escrow(
+        ctx: Context<CreateVoterEscrow>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.project = ctx.accounts.base_account.key();
+        escrow.voter = ctx.accounts.voter.key();
+        escrow.mint = ctx.accounts.governance_mint.key();
+        escrow.amount = 0;
+        escrow.locked_until = 0;
+        escrow.bump = *ctx.bumps.get("escrow").unwrap();
+
+        Ok(())
+    }
+
+    pub fn deposit_governance_tokens(
+        ctx: Context<DepositGovernanceTokens>,
+        _base_bump: u8,
+        _project_id: String,
+        amount: u64,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let escrow = &mut ctx.accounts.escrow;
+
+        if parameters.governance_mint != Some(escrow.mint) {
+            return Err(error!(ErrorCode::InvalidGovernanceMint));
+        }
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.voter_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.voter.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_instruction,
+        );
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        escrow.amount += amount;
+
+        Ok(())
+    }
+
+    pub fn withdraw_governance_tokens(
+        ctx: Context<WithdrawGovernanceTokens>,
+        _base_bump: u8,
+        _project_id: String,
+        amount: u64,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let escrow = &mut ctx.accounts.escrow;
+
+        // Tokens stay locked until every proposal they voted on has expired
+        if Clock::get().unwrap().unix_timestamp <= escrow.locked_until {
+            return Err(error!(ErrorCode::EscrowLocked));
+        }
+
+        if amount > escrow.amount {
+            return Err(error!(ErrorCode::InsufficientDeposit));
+        }
+
+        let project_key = parameters.key();
+        let mint_key = escrow.mint;
+        let voter_key = escrow.voter;
+        let bump_vector = escrow.bump.to_le_bytes();
+        let inner = vec![
+            ESCROW_SEED,
+            project_key.as_ref(),
+            mint_key.as_ref(),
+            voter_key.as_ref(),
+            bump_vector.as_ref(),
+        ];
+        let outer = vec![inner.as_slice()];
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.voter_token_account.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_instruction,
+            outer.as_slice(),
+        );
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        escrow.amount -= amount;
+
+        Ok(())
+    }
+
+    pub fn cast_token_vote(
+        ctx: Context<CastTokenVote>,
+        _base_bump: u8,
+        _project_id: String,
+        kind: ProposalKind,
+        approve: bool,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+        let escrow = &mut ctx.accounts.escrow;
+
+        if parameters.governance_mint.is_none() {
+            return Err(error!(ErrorCode::TokenVotingDisabled));
+        }
+
+        if parameters.governance_mint != Some(escrow.mint) {
+            return Err(error!(ErrorCode::InvalidGovernanceMint));
+        }
+
+        if escrow.amount == 0 {
+            return Err(error!(ErrorCode::InsufficientDeposit));
+        }
+
+        check_open_for_votes(parameters, proposal, kind)?;
+
+        // The vote record can only be created once, so every escrow votes once per proposal
+        let record = &mut ctx.accounts.vote_record;
+        record.proposal = proposal.key();
+        record.voter = escrow.voter;
+        record.weight = escrow.amount;
+        record.approve = approve;
+        record.bump = *ctx.bumps.get("vote_record").unwrap();
+
+        if escrow.locked_until < proposal.expires_at {
+            escrow.locked_until = proposal.expires_at;
+        }
+
+        if approve {
+            proposal.votes += 1;
+            proposal.approval_weight += escrow.amount;
+            approve_if_reached(parameters, proposal);
+        } else {
+            proposal.rejections += 1;
+            proposal.rejection_weight += escrow.amount;
+            reject_if_unreachable(parameters, proposal);
+        }
+
+        Ok(())
+    }
+
+    pub fn object_proposal(
+        ctx: Context<SignProposal>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+
+        // Token holders decide on their own, the members keep no veto over them
+        if parameters.governance_mint.is_some() {
+            return Err(error!(ErrorCode::TokenVotingEnabled));
+        }
+
+        parameters.check_permission(ctx.accounts.authority.key(), PERMISSION_VETO)?;
+
+        if proposal.state != ProposalState::Approved {
+            return Err(error!(ErrorCode::ProposalNotApproved));
+        }
+
+        // A single member can veto an approved proposal while it is waiting for the execution delay
+        if Clock::get().unwrap().unix_timestamp >= proposal.executable_at {
+            return Err(error!(ErrorCode::ObjectionWindowPassed));
+        }
+
+        msg!("the proposal has been vetoed by a member");
+        proposal.state = ProposalState::Cancelled;
+
+        Ok(())
+    }
+
+    pub fn expire_proposal(
+        ctx: Context<ExpireProposal>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &ctx.accounts.base_account;
+        let proposal = &mut ctx.accounts.proposal;
+        let stale = proposal.is_stale(parameters.config_generation);
+
+        match proposal.state {
+            ProposalState::Draft | ProposalState::Active => {}
+            // approved proposals only expire when the configuration changed under them
+            ProposalState::Approved if stale => {}
+            _ => return Err(error!(ErrorCode::NoProposalCreated)),
+        }
+
+        if !stale && !proposal.is_expired() {
+            return Err(error!(ErrorCode::ProposalNotExpired));
+        }
+
+        msg!("the proposal has expired");
+        proposal.state = ProposalState::Expired;
+
+        Ok(())
+    }
+
+    pub fn close_proposal(
+        ctx: Context<CloseProposal>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        // The rent is returned to the proposer by the close constraint
+        if !ctx.accounts.proposal.is_finished() {
+            return Err(error!(ErrorCode::ProposalNotFinished));
+        }
+
+        Ok(())
+    }
+
+    pub fn deposit_funds(
+        ctx: Context<Deposit>,
+        project_id: String,
+        project_bump: u8,
+        _pool_bump: u8,
+        _general_bump: u8,
+        amount: u32,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+        let general_parameters = &mut ctx.accounts.general_account;
+
+        if general_parameters.token_mint == ctx.accounts.token_mint.key() {
+            msg!("Mint is matching");
+
+            let bump_vector = project_bump.to_le_bytes();
+            let inner = vec![
+                PROJECT_SEED,
+                project_id.as_bytes()[..18].as_ref(),
+                project_id.as_bytes()[18..].as_ref(),
+                bump_vector.as_ref(),
+            ];
+            let outer = vec![inner.as_slice()];
+
+            let amount_to_transfer =
+                (amount * (100 - (parameters.percent_transfer as u32)) / 100) as u64;
+            let amount_to_deduct = (amount - (amount_to_transfer as u32)) as u64;
+
+            // Below is the actual instruction that we are going to send to the Token program.
+            let transfer_instruction = Transfer {
+                from: ctx.accounts.wallet_to_withdraw_from.to_account_info(),
+                to: ctx.accounts.project_pool_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+                outer.as_slice(), //signer PDA
+            );
+
+            let another_transfer_instruction = Transfer {
+                from: ctx.accounts.wallet_to_withdraw_from.to_account_info(),
+                to: ctx.accounts.admin_token_wallet.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+
+            let another_cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                another_transfer_instruction,
+                outer.as_slice(), //signer PDA
+            );
+
+            msg!("{} {}", amount_to_transfer, amount_to_deduct);
+
+            // The `?` at the end will cause the function to return early in case of an error.
+            // This pattern is common in Rust.
+            anchor_spl::token::transfer(cpi_ctx, amount_to_transfer)?;
+            anchor_spl::token::transfer(another_cpi_ctx, amount_to_deduct)?;
+
+            parameters.staked_amount += amount_to_transfer as u32;
+        }
+
+        Ok(())
+    }
+
+    pub fn fall_back(
+        ctx: Context<FallBack>,
+        _base_bump: u8,
+        _project_id: String,
+    ) -> Result<()> {
+        let parameters = &mut ctx.accounts.base_account;
+
+        let day: i32 = 60 * 60 * 24; // 1 day
+        let current_time = Clock::get().unwrap().unix_timestamp as i32;
+
+        if !parameters.shutdown {
+            if (current_time - parameters.last_tx) / day >= 90 {
+                parameters.shutdown = true;
                 if parameters.threshold > 1 {
                     parameters.threshold -= 1;
                     parameters.last_reduced_threshold = current_time;
@@ -578,28 +1715,237 @@ pub mod project {
             }
         }
 
-        Ok(())
-    }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(project_id: String)]
+pub struct Initialize<'info> {
+    #[account(init, payer = authority, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump, space = PROJECT_INITIAL_SPACE)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(
+        init, payer = authority,
+        seeds = [POOL_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()],
+        bump,
+        token::mint=token_mint,
+        token::authority=base_account,
+    )]
+    pub project_pool_account: Account<'info, TokenAccount>,
+    pub token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK:
+    pub admin: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct AddInitialSignatories<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, constraint = authority.key() == base_account.signatories[0].key @ErrorCode::InvalidSigner)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed, constraint = base_account.can_propose(authority.key()) @ErrorCode::InvalidSigner)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = authority, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), base_account.proposal_count.to_le_bytes().as_ref()], bump, space = PROPOSAL_SPACE)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CreateProjectSignatoryProposal<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed, constraint = base_account.can_propose(authority.key()) @ErrorCode::InvalidSigner)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = authority, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), base_account.proposal_count.to_le_bytes().as_ref()], bump, space = PROPOSAL_SPACE)]
+    pub proposal: Account<'info, Proposal>,
+    pub child_project: Account<'info, ProjectParameter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String, instruction: ProposalInstruction)]
+pub struct CreateInstructionProposal<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed, constraint = base_account.can_propose(authority.key()) @ErrorCode::InvalidSigner)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = authority, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), base_account.proposal_count.to_le_bytes().as_ref()], bump, space = PROPOSAL_BASE_SPACE + 1 + instruction.size())]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String, actions: Vec<BatchAction>)]
+pub struct CreateBatchProposal<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed, constraint = base_account.can_propose(authority.key()) @ErrorCode::InvalidSigner)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = authority, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), base_account.proposal_count.to_le_bytes().as_ref()], bump, space = PROPOSAL_BASE_SPACE + 1 + BatchAction::batch_size(&actions))]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct AddBatchAction<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, constraint = proposal.proposer == authority.key() @ErrorCode::InvalidSigner)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct ActivateProposal<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, constraint = proposal.proposer == authority.key() @ErrorCode::InvalidSigner)]
+    pub proposal: Account<'info, Proposal>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(project_id: String)]
-pub struct Initialize<'info> {
-    #[account(init, payer = authority, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump, space = 1800)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct SignProposal<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, constraint = proposal.project == base_account.key() @ErrorCode::InvalidProposal)]
+    pub proposal: Account<'info, Proposal>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct ManageDelegation<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct RequestKeyRotation<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = new_authority, seeds = [ROTATION_SEED, base_account.key().as_ref(), authority.key().as_ref()], bump, space = KEY_ROTATION_SPACE)]
+    pub rotation: Account<'info, KeyRotation>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub new_authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CompleteKeyRotation<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [ROTATION_SEED, base_account.key().as_ref(), rotation.old_key.as_ref()], bump = rotation.bump, constraint = rotation.new_key == new_authority.key() @ErrorCode::InvalidSigner, close = new_authority)]
+    pub rotation: Account<'info, KeyRotation>,
+    #[account(mut)]
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct ObjectKeyRotation<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [ROTATION_SEED, base_account.key().as_ref(), rotation.old_key.as_ref()], bump = rotation.bump, has_one = new_key, close = new_key)]
+    pub rotation: Account<'info, KeyRotation>,
+    /// CHECK: only receives the rent of the rotation request it paid for
+    #[account(mut)]
+    pub new_key: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String, lost_key: Pubkey)]
+pub struct StartRecovery<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = guardian, seeds = [RECOVERY_SEED, base_account.key().as_ref(), lost_key.as_ref()], bump, space = RECOVERY_SPACE)]
+    pub recovery: Account<'info, Recovery>,
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct ApproveRecovery<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [RECOVERY_SEED, base_account.key().as_ref(), recovery.lost_key.as_ref()], bump = recovery.bump)]
+    pub recovery: Account<'info, Recovery>,
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CompleteRecovery<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [RECOVERY_SEED, base_account.key().as_ref(), recovery.lost_key.as_ref()], bump = recovery.bump, has_one = initiator, close = initiator)]
+    pub recovery: Account<'info, Recovery>,
+    /// CHECK: only receives the rent of the recovery it paid for
+    #[account(mut)]
+    pub initiator: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CancelRecovery<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [RECOVERY_SEED, base_account.key().as_ref(), recovery.lost_key.as_ref()], bump = recovery.bump, has_one = initiator, close = initiator)]
+    pub recovery: Account<'info, Recovery>,
+    /// CHECK: only receives the rent of the recovery it paid for
+    #[account(mut)]
+    pub initiator: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CreateVoterEscrow<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = base_account.governance_mint == Some(governance_mint.key()) @ErrorCode::InvalidGovernanceMint)]
     pub base_account: Account<'info, ProjectParameter>,
+    #[account(init, payer = voter, seeds = [ESCROW_SEED, base_account.key().as_ref(), governance_mint.key().as_ref(), voter.key().as_ref()], bump, space = VOTER_ESCROW_SPACE)]
+    pub escrow: Account<'info, VoterEscrow>,
     #[account(
-        init, payer = authority,
-        seeds = [POOL_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()],
+        init, payer = voter,
+        seeds = [ESCROW_VAULT_SEED, base_account.key().as_ref(), governance_mint.key().as_ref(), voter.key().as_ref()],
         bump,
-        token::mint=token_mint,
-        token::authority=base_account,
+        token::mint=governance_mint,
+        token::authority=escrow,
     )]
-    pub project_pool_account: Account<'info, TokenAccount>,
-    pub token_mint: Account<'info, Mint>,
+    pub vault: Account<'info, TokenAccount>,
+    pub governance_mint: Account<'info, Mint>,
     #[account(mut)]
-    pub authority: Signer<'info>,
-    /// CHECK:
-    pub admin: AccountInfo<'info>,
+    pub voter: Signer<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
@@ -607,30 +1953,81 @@ pub struct Initialize<'info> {
 
 #[derive(Accounts)]
 #[instruction(base_bump: u8, project_id: String)]
-pub struct AddInitialSignatories<'info> {
-    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+pub struct DepositGovernanceTokens<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
     pub base_account: Account<'info, ProjectParameter>,
-    #[account(mut, constraint = authority.key() == base_account.signatories[0].key @ErrorCode::InvalidSigner)]
-    pub authority: Signer<'info>,
-    // pub system_program: Program<'info, System>
+    #[account(mut, seeds = [ESCROW_SEED, base_account.key().as_ref(), escrow.mint.as_ref(), voter.key().as_ref()], bump = escrow.bump, has_one = voter)]
+    pub escrow: Account<'info, VoterEscrow>,
+    #[account(mut, seeds = [ESCROW_VAULT_SEED, base_account.key().as_ref(), escrow.mint.as_ref(), voter.key().as_ref()], bump)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 #[instruction(base_bump: u8, project_id: String)]
-pub struct Proposal<'info> {
-    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, has_one = authority)]
+pub struct WithdrawGovernanceTokens<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
     pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [ESCROW_SEED, base_account.key().as_ref(), escrow.mint.as_ref(), voter.key().as_ref()], bump = escrow.bump, has_one = voter)]
+    pub escrow: Account<'info, VoterEscrow>,
+    #[account(mut, seeds = [ESCROW_VAULT_SEED, base_account.key().as_ref(), escrow.mint.as_ref(), voter.key().as_ref()], bump)]
+    pub vault: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub voter_token_account: Account<'info, TokenAccount>,
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 #[instruction(base_bump: u8, project_id: String)]
-pub struct SignProposal<'info> {
-    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+pub struct CastTokenVote<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, constraint = proposal.project == base_account.key() @ErrorCode::InvalidProposal)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut, seeds = [ESCROW_SEED, base_account.key().as_ref(), escrow.mint.as_ref(), voter.key().as_ref()], bump = escrow.bump, has_one = voter)]
+    pub escrow: Account<'info, VoterEscrow>,
+    #[account(init, payer = voter, seeds = [VOTE_SEED, proposal.key().as_ref(), voter.key().as_ref()], bump, space = VOTE_RECORD_SPACE)]
+    pub vote_record: Account<'info, VoteRecord>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed)]
     pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, constraint = proposal.project == base_account.key() @ErrorCode::InvalidProposal)]
+    pub proposal: Account<'info, Proposal>,
     #[account(mut)]
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct ExpireProposal<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, constraint = proposal.project == base_account.key() @ErrorCode::InvalidProposal)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(base_bump: u8, project_id: String)]
+pub struct CloseProposal<'info> {
+    #[account(seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = base_bump)]
+    pub base_account: Account<'info, ProjectParameter>,
+    #[account(mut, seeds = [PROPOSAL_SEED, base_account.key().as_ref(), proposal.index.to_le_bytes().as_ref()], bump = proposal.bump, has_one = proposer, close = proposer)]
+    pub proposal: Account<'info, Proposal>,
+    /// CHECK: only receives the rent of the closed proposal
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -645,7 +2042,7 @@ pub struct FallBack<'info> {
 #[derive(Accounts)]
 #[instruction(project_id: String, project_bump: u8, pool_bump: u8, general_bump: u8)]
 pub struct Deposit<'info> {
-    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = project_bump)]
+    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = project_bump, constraint = !base_account.closed @ErrorCode::ProjectClosed)]
     pub base_account: Account<'info, ProjectParameter>,
     #[account(mut, seeds = [GENERAL_SEED], bump = general_bump, seeds::program = general_program.key())]
     pub general_account: Account<'info, GeneralParameter>,
@@ -670,105 +2067,581 @@ pub struct Deposit<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-#[derive(Accounts)]
-#[instruction(general_bump: u8, project_bump: u8, pool_bump : u8, project_id: String)]
-pub struct SignTransfer<'info> {
-    #[account(mut, seeds = [PROJECT_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()], bump = project_bump)]
-    pub base_account: Account<'info, ProjectParameter>,
-    #[account(mut, seeds = [GENERAL_SEED], bump = general_bump, seeds::program = general_program.key())]
-    pub general_account: Account<'info, GeneralParameter>,
-    #[account(
-        mut,
-        seeds = [POOL_SEED, project_id.as_bytes()[..18].as_ref(), project_id.as_bytes()[18..].as_ref()],
-        bump = pool_bump,
-        token::mint=token_mint,
-        token::authority=base_account,
-    )]
-    pub project_pool_account: Box<Account<'info, TokenAccount>>,
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(mut)]
-    pub wallet_to_withdraw_from: Account<'info, TokenAccount>,
-    pub general_program: Program<'info, General>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct Signature {
+    pub key: Pubkey,          // 32
+    pub permissions: u8,      // 1
+    pub weight: u32,          // 4
+    pub previous_key: Pubkey, // 32
+    pub is_project: bool,     // 1
 }
 
 #[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct Signature {
-    pub key: Pubkey,             // 32
-    pub add: bool,               // 1
-    pub delete: bool,            // 1
-    pub change_threshold: bool,  // 1
-    pub change_time_limit: bool, // 1
-    pub transfer_amount: bool,   // 1
+pub struct InstructionAccount {
+    pub pubkey: Pubkey,    // 32
+    pub is_signer: bool,   // 1
+    pub is_writable: bool, // 1
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,                // 32
+    pub accounts: Vec<InstructionAccount>, // 4 + 34 * n
+    pub data: Vec<u8>,                     // 4 + n
+}
+
+impl ProposalInstruction {
+    pub fn size(&self) -> usize {
+        32 + 4 + 34 * self.accounts.len() + 4 + self.data.len()
+    }
+
+    pub fn to_instruction(&self) -> Instruction {
+        let mut accounts = Vec::new();
+
+        for account in &self.accounts {
+            if account.is_writable {
+                accounts.push(AccountMeta::new(account.pubkey, account.is_signer));
+            } else {
+                accounts.push(AccountMeta::new_readonly(account.pubkey, account.is_signer));
+            }
+        }
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: self.data.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum BatchAction {
+    TransferAmount { amount: u32, reciever: Pubkey }, // 4 + 32
+    AddSignatory { new_signatory: Vec<Pubkey> },      // 4 + 32*n
+    DeleteSignatory { old_signatory: Vec<Pubkey> },   // 4 + 32*n
+    Instruction { instruction: ProposalInstruction }, // 32 + 4 + 34*n + 4 + n
+}
+
+impl BatchAction {
+    pub fn size(&self) -> usize {
+        match self {
+            BatchAction::TransferAmount { .. } => 1 + 4 + 32,
+            BatchAction::AddSignatory { new_signatory } => 1 + 4 + 32 * new_signatory.len(),
+            BatchAction::DeleteSignatory { old_signatory } => 1 + 4 + 32 * old_signatory.len(),
+            BatchAction::Instruction { instruction } => 1 + instruction.size(),
+        }
+    }
+
+    pub fn kind(&self) -> ProposalKind {
+        match self {
+            BatchAction::TransferAmount { .. } => ProposalKind::TransferAmount,
+            BatchAction::AddSignatory { .. } => ProposalKind::AddSignatory,
+            BatchAction::DeleteSignatory { .. } => ProposalKind::DeleteSignatory,
+            BatchAction::Instruction { .. } => ProposalKind::Instruction,
+        }
+    }
+
+    pub fn batch_size(actions: &[BatchAction]) -> usize {
+        4 + actions.iter().map(|action| action.size()).sum::<usize>()
+    }
+
+    pub fn total_amount(actions: &[BatchAction]) -> u64 {
+        actions
+            .iter()
+            .map(|action| match action {
+                BatchAction::TransferAmount { amount, .. } => *amount as u64,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum ProposalAction {
+    AddSignatory { new_signatory: Vec<Pubkey>, weights: Vec<u32> },         // 4 + 32*10 + 4 + 4*10
+    AddProjectSignatory { project: Pubkey },                                // 32
+    DeleteSignatory { old_signatory: Vec<Pubkey> },                         // 4 + 32*10
+    ReplaceSignatory { old_signatory: Pubkey, new_signatory: Pubkey },      // 32 + 32
+    ChangeThreshold { new_threshold: u32 },                                 // 4
+    ChangeTimeLimit { new_time_limit: u32 },                                // 4
+    ChangeExecutionDelay { new_delay: u32 },                                // 4
+    ChangeKindThresholds { thresholds: Vec<KindThreshold> },                // 4 + 5*n
+    ChangeThresholdPolicy { policy: Option<ThresholdPolicy> },              // 1 + 9
+    ChangeAmountTiers { tiers: Vec<AmountTier> },                           // 4 + 48*n
+    ChangePermissions { signatory: Pubkey, permissions: u8 },               // 32 + 1
+    ChangeWeight { signatory: Pubkey, weight: u32 },                        // 32 + 4
+    ChangeGuardians { guardians: Vec<Pubkey>, threshold: u32, delay: u32 }, // 4 + 32*5 + 4 + 4
+    ChangeMaxMembers { max_members: u32 },                                  // 4
+    ChangeGovernance { mint: Option<Pubkey>, threshold: u64 },              // 1 + 32 + 8
+    TransferAmount { amount: u32, reciever: Pubkey },                       // 4 + 32
+    Shutdown,
+    Instruction { instruction: ProposalInstruction },                       // 32 + 4 + 34*n + 4 + n
+    Batch { actions: Vec<BatchAction> },                                    // 4 + actions
+}
+
+impl ProposalAction {
+    pub fn kind(&self) -> ProposalKind {
+        match self {
+            ProposalAction::AddSignatory { .. } => ProposalKind::AddSignatory,
+            ProposalAction::AddProjectSignatory { .. } => ProposalKind::AddProjectSignatory,
+            ProposalAction::DeleteSignatory { .. } => ProposalKind::DeleteSignatory,
+            ProposalAction::ReplaceSignatory { .. } => ProposalKind::ReplaceSignatory,
+            ProposalAction::ChangeThreshold { .. } => ProposalKind::ChangeThreshold,
+            ProposalAction::ChangeTimeLimit { .. } => ProposalKind::ChangeTimeLimit,
+            ProposalAction::ChangeExecutionDelay { .. } => ProposalKind::ChangeExecutionDelay,
+            ProposalAction::ChangeKindThresholds { .. } => ProposalKind::ChangeKindThresholds,
+            ProposalAction::ChangeThresholdPolicy { .. } => ProposalKind::ChangeThresholdPolicy,
+            ProposalAction::ChangeAmountTiers { .. } => ProposalKind::ChangeAmountTiers,
+            ProposalAction::ChangePermissions { .. } => ProposalKind::ChangePermissions,
+            ProposalAction::ChangeWeight { .. } => ProposalKind::ChangeWeight,
+            ProposalAction::ChangeGuardians { .. } => ProposalKind::ChangeGuardians,
+            ProposalAction::ChangeMaxMembers { .. } => ProposalKind::ChangeMaxMembers,
+            ProposalAction::ChangeGovernance { .. } => ProposalKind::ChangeGovernance,
+            ProposalAction::TransferAmount { .. } => ProposalKind::TransferAmount,
+            ProposalAction::Shutdown => ProposalKind::Shutdown,
+            ProposalAction::Instruction { .. } => ProposalKind::Instruction,
+            ProposalAction::Batch { .. } => ProposalKind::Batch,
+        }
+    }
+
+    pub fn changes_config(&self) -> bool {
+        match self {
+            ProposalAction::AddSignatory { .. }
+            | ProposalAction::AddProjectSignatory { .. }
+            | ProposalAction::DeleteSignatory { .. }
+            | ProposalAction::ReplaceSignatory { .. }
+            | ProposalAction::ChangeThreshold { .. }
+            | ProposalAction::ChangeThresholdPolicy { .. }
+            | ProposalAction::ChangeKindThresholds { .. }
+            | ProposalAction::ChangeAmountTiers { .. }
+            | ProposalAction::ChangePermissions { .. }
+            | ProposalAction::ChangeWeight { .. }
+            | ProposalAction::ChangeGovernance { .. } => true,
+            ProposalAction::Batch { actions } => actions.iter().any(|action| {
+                matches!(
+                    action,
+                    BatchAction::AddSignatory { .. } | BatchAction::DeleteSignatory { .. }
+                )
+            }),
+            _ => false,
+        }
+    }
+
+    // The amount moved out of the project pool, a batch counts all of its transfers together
+    pub fn transfer_amount(&self) -> u64 {
+        match self {
+            ProposalAction::TransferAmount { amount, .. } => *amount as u64,
+            ProposalAction::Batch { actions } => BatchAction::total_amount(actions),
+            _ => 0,
+        }
+    }
+
+    // Instructions run with the project as signer, which also holds the authority of the pool
+    pub fn invokes_instructions(&self) -> bool {
+        match self {
+            ProposalAction::Instruction { .. } => true,
+            ProposalAction::Batch { actions } => actions
+                .iter()
+                .any(|action| matches!(action, BatchAction::Instruction { .. })),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum ProposalKind {
+    AddSignatory,
+    AddProjectSignatory,
+    DeleteSignatory,
+    ReplaceSignatory,
+    ChangeThreshold,
+    ChangeTimeLimit,
+    ChangeExecutionDelay,
+    ChangeKindThresholds,
+    ChangeThresholdPolicy,
+    ChangeAmountTiers,
+    ChangePermissions,
+    ChangeWeight,
+    ChangeGuardians,
+    ChangeMaxMembers,
+    ChangeGovernance,
+    TransferAmount,
+    Shutdown,
+    Instruction,
+    Batch,
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct KindThreshold {
+    pub kind: ProposalKind, // 1
+    pub threshold: u32,     // 4
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct Delegation {
+    pub delegator: Pubkey,          // 32
+    pub delegate: Pubkey,           // 32
+    pub kind: Option<ProposalKind>, // 1 + 1
+    pub expires_at: i64,            // 8
+}
+
+impl Delegation {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at == 0 || now < self.expires_at
+    }
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct AmountTier {
+    pub mint: Pubkey,    // 32
+    pub min_amount: u64, // 8
+    pub threshold: u32,  // 4
+    pub delay: u32,      // 4
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct ThresholdPolicy {
+    pub percent: u8,  // 1
+    pub floor: u32,   // 4
+    pub ceiling: u32, // 4
+}
+
+impl ThresholdPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if self.percent == 0 || self.percent > 100 || self.floor == 0 || self.floor > self.ceiling {
+            return Err(error!(ErrorCode::InvalidThresholdPolicy));
+        }
+        Ok(())
+    }
+
+    // ceil(percent * total weight) kept between the floor and the ceiling, but never above the
+    // total weight. The product is taken in u64 since the total weight can reach 112 million
+    pub fn threshold(&self, total_weight: u32) -> u32 {
+        let threshold = (u64::from(self.percent) * u64::from(total_weight)).div_ceil(100);
+        (threshold as u32).max(self.floor).min(self.ceiling).min(total_weight)
+    }
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct ProposalMetadata {
+    pub title: String,              // 4 + 64
+    pub description_uri: String,    // 4 + 200
+    pub description_hash: [u8; 32], // 32
+}
+
+impl ProposalMetadata {
+    pub fn validate(&self) -> Result<()> {
+        if self.title.len() > MAX_TITLE_LENGTH {
+            return Err(error!(ErrorCode::TitleTooLong));
+        }
+        if self.description_uri.len() > MAX_DESCRIPTION_URI_LENGTH {
+            return Err(error!(ErrorCode::DescriptionUriTooLong));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum ProposalState {
+    Draft,
+    Active,
+    Approved,
+    Rejected,
+    Executed,
+    Cancelled,
+    Expired,
+}
+
+const MAX_AMOUNT_TIERS: usize = 10;
+
+// Delegations are loaded with the project on every vote, so their total stays small enough for
+// the heap, and each member only gets a share of it so that no one can take up every entry
+const MAX_DELEGATIONS: usize = 50;
+const MAX_DELEGATIONS_PER_MEMBER: usize = 3;
+
+// Keeps the total weight of a full project, at most 112 million, within a u32
+const MAX_WEIGHT: u32 = 1_000_000;
+
+// Proposals track votes in bitmaps with a bit for every member slot. Borsh reserves at most
+// 4096 bytes for a vector it reads and doubles the space from there, and the heap never frees,
+// so the members are kept to two such steps to leave room in the 32 KB heap for the rest
+const VOTE_BITMAP_SIZE: usize = 14;
+const MAX_MEMBERS: u32 = (VOTE_BITMAP_SIZE * 8) as u32;
+const _: () = assert!(MAX_MEMBERS as usize <= 2 * (4096 / std::mem::size_of::<Signature>()));
+const DEFAULT_MAX_MEMBERS: u32 = 50;
+const MAX_SIGNATORIES_PER_PROPOSAL: usize = 10;
+
+const MAX_GUARDIANS: usize = 5;
+const MIN_RECOVERY_DELAY: u32 = 60 * 60 * 24 * 7;
+
+const MAX_TITLE_LENGTH: usize = 64;
+const MAX_DESCRIPTION_URI_LENGTH: usize = 200;
+// title + description uri + description hash
+const METADATA_SPACE: usize = 4 + MAX_TITLE_LENGTH + 4 + MAX_DESCRIPTION_URI_LENGTH + 32;
+
+// discriminator + project + index + proposer + metadata + timestamp + expires_at + votes
+// + approval_weight + required_votes + voting_weight + voters + rejections + rejection_weight
+// + rejectors + cancellations + cancellation_weight + cancellers + state + config_generation
+// + approved_at + executable_at + bump
+const PROPOSAL_BASE_SPACE: usize = 8 + 32 + 8 + 32 + METADATA_SPACE + 8 + 8 + 4 + 8 + 4 + 8
+    + VOTE_BITMAP_SIZE + 4 + 8 + VOTE_BITMAP_SIZE + 4 + 8 + VOTE_BITMAP_SIZE + 1 + 8 + 8 + 8 + 1;
+// enum tag + the largest of the fixed size actions, a full set of amount tiers
+const PROPOSAL_SPACE: usize = PROPOSAL_BASE_SPACE + 1 + 4 + 48 * MAX_AMOUNT_TIERS;
+
+#[account]
+pub struct Proposal {
+    pub project: Pubkey,            // 32
+    pub index: u64,                 // 8
+    pub proposer: Pubkey,           // 32
+    pub metadata: ProposalMetadata, // 4 + 64 + 4 + 200 + 32
+    pub action: ProposalAction,     // 1 + 484, or more for instructions and batches
+    pub timestamp: i64,             // 8
+    pub expires_at: i64,            // 8
+    pub votes: u32,                 // 4
+    pub approval_weight: u64,       // 8
+    pub required_votes: u32,        // 4
+    pub voting_weight: u64,         // 8
+    pub voters: [u8; 14],           // 14
+    pub rejections: u32,            // 4
+    pub rejection_weight: u64,      // 8
+    pub rejectors: [u8; 14],        // 14
+    pub cancellations: u32,         // 4
+    pub cancellation_weight: u64,   // 8
+    pub cancellers: [u8; 14],       // 14
+    pub state: ProposalState,       // 1
+    pub config_generation: u64,     // 8
+    pub approved_at: i64,           // 8
+    pub executable_at: i64,         // 8
+    pub bump: u8,                   // 1
+}
+
+// What a new proposal takes over from its project and the account creating it
+pub struct ProposalOrigin {
+    pub project: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub bump: u8,
+    pub time_limit: u32,
+    pub config_generation: u64,
+    pub voting_weight: u64,
+}
+
+impl ProposalOrigin {
+    pub fn new(parameters: &Account<ProjectParameter>, proposer: Pubkey, bump: u8) -> Self {
+        ProposalOrigin {
+            project: parameters.key(),
+            index: parameters.proposal_count,
+            proposer,
+            bump,
+            time_limit: parameters.time_limit,
+            config_generation: parameters.config_generation,
+            voting_weight: u64::from(parameters.total_weight()),
+        }
+    }
+}
+
+impl Proposal {
+    pub fn create(
+        &mut self,
+        origin: ProposalOrigin,
+        action: ProposalAction,
+        metadata: ProposalMetadata,
+    ) -> Result<()> {
+        metadata.validate()?;
+
+        self.project = origin.project;
+        self.index = origin.index;
+        self.proposer = origin.proposer;
+        self.metadata = metadata;
+        self.action = action;
+        self.timestamp = Clock::get().unwrap().unix_timestamp;
+        self.expires_at = self.timestamp + origin.time_limit as i64;
+        self.config_generation = origin.config_generation;
+        self.votes = 0;
+        self.approval_weight = 0;
+        self.required_votes = 0;
+        self.voting_weight = origin.voting_weight;
+        self.voters = [0; VOTE_BITMAP_SIZE];
+        self.rejections = 0;
+        self.rejection_weight = 0;
+        self.rejectors = [0; VOTE_BITMAP_SIZE];
+        self.cancellations = 0;
+        self.cancellation_weight = 0;
+        self.cancellers = [0; VOTE_BITMAP_SIZE];
+        self.state = ProposalState::Active;
+        self.approved_at = 0;
+        self.executable_at = 0;
+        self.bump = origin.bump;
+        Ok(())
+    }
+
+    pub fn approve(&mut self, execution_delay: u32) {
+        self.state = ProposalState::Approved;
+        self.approved_at = Clock::get().unwrap().unix_timestamp;
+        self.executable_at = self.approved_at + execution_delay as i64;
+    }
+
+    // Votes are kept by member slot. Slots only move when members are removed, which makes
+    // every open proposal stale, and a rotated key keeps its slot along with its votes.
+    pub fn has_voted(&self, slot: usize) -> bool {
+        slot_is_set(&self.voters, slot) || slot_is_set(&self.rejectors, slot)
+    }
+
+    pub fn sign(&mut self, slot: usize, weight: u64) -> Result<()> {
+        if self.has_voted(slot) {
+            return Err(error!(ErrorCode::RepeatedSignature));
+        }
+        set_slot(&mut self.voters, slot, true);
+        self.votes += 1;
+        self.approval_weight += weight;
+        Ok(())
+    }
+
+    // Weight changes make open proposals stale, so this is the weight the vote was cast with
+    pub fn revoke(&mut self, slot: usize, weight: u64) -> Result<()> {
+        if !slot_is_set(&self.voters, slot) {
+            return Err(error!(ErrorCode::NoVoteToRevoke));
+        }
+        set_slot(&mut self.voters, slot, false);
+        self.votes -= 1;
+        self.approval_weight = self.approval_weight.saturating_sub(weight);
+        Ok(())
+    }
+
+    pub fn reject(&mut self, slot: usize, weight: u64) -> Result<()> {
+        if self.has_voted(slot) {
+            return Err(error!(ErrorCode::RepeatedSignature));
+        }
+        set_slot(&mut self.rejectors, slot, true);
+        self.rejections += 1;
+        self.rejection_weight += weight;
+        Ok(())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Clock::get().unwrap().unix_timestamp > self.expires_at
+    }
+
+    // Drafts take the configuration they are voted under when they are activated
+    pub fn is_stale(&self, config_generation: u64) -> bool {
+        self.state != ProposalState::Draft && self.config_generation != config_generation
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state == ProposalState::Executed
+            || self.state == ProposalState::Rejected
+            || self.state == ProposalState::Expired
+            || self.state == ProposalState::Cancelled
+    }
 }
 
-#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct AddSignatory {
-    pub status: bool,               // 1
-    pub new_signatory: Vec<Pubkey>, // 32*10
-    pub timestamp: i64,             // 8
-    pub votes: u32,                 // 4
-}
+const KEY_ROTATION_SPACE: usize = 8 + 32 + 32 + 32 + 8 + 1;
 
-#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct DeleteSignatory {
-    pub status: bool,               // 1
-    pub old_signatory: Vec<Pubkey>, // 32*10
-    pub timestamp: i64,             // 8
-    pub votes: u32,                 // 4
+#[account]
+pub struct KeyRotation {
+    pub project: Pubkey,    // 32
+    pub old_key: Pubkey,    // 32
+    pub new_key: Pubkey,    // 32
+    pub executable_at: i64, // 8
+    pub bump: u8,           // 1
 }
 
-#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct ChangeThreshold {
-    pub status: bool,       // 1
-    pub new_threshold: u32, // 4
-    pub timestamp: i64,     // 8
-    pub votes: u32,         // 4
+const RECOVERY_SPACE: usize = 8 + 32 + 32 + 32 + 32 + (4 + 32 * MAX_GUARDIANS) + 8 + 1;
+
+#[account]
+pub struct Recovery {
+    pub project: Pubkey,        // 32
+    pub lost_key: Pubkey,       // 32
+    pub new_key: Pubkey,        // 32
+    pub initiator: Pubkey,      // 32
+    pub approvals: Vec<Pubkey>, // 4 + 32 * 5
+    pub executable_at: i64,     // 8
+    pub bump: u8,               // 1
 }
-#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct ChangeTimeLimit {
-    pub status: bool,        // 1
-    pub new_time_limit: u32, // 4
-    pub timestamp: i64,      // 8
-    pub votes: u32,          // 4
+
+impl Recovery {
+    // The recovery delay starts once the guardian threshold is first reached
+    pub fn approve(&mut self, parameters: &ProjectParameter, guardian: Pubkey) -> Result<()> {
+        if self.approvals.contains(&guardian) {
+            return Err(error!(ErrorCode::RepeatedSignature));
+        }
+        // Approvals of removed guardians no longer count, dropping them keeps the list within
+        // the MAX_GUARDIANS the account has room for
+        self.approvals.retain(|key| parameters.guardians.contains(key));
+        self.approvals.push(guardian);
+
+        if self.executable_at == 0
+            && self.guardian_approvals(parameters) >= parameters.guardian_threshold
+        {
+            let now = Clock::get().unwrap().unix_timestamp;
+            self.executable_at = now + i64::from(parameters.recovery_delay);
+        }
+        Ok(())
+    }
+
+    pub fn guardian_approvals(&self, parameters: &ProjectParameter) -> u32 {
+        let approvals = self.approvals.iter();
+        approvals.filter(|key| parameters.guardians.contains(key)).count() as u32
+    }
 }
-#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct TransferAmount {
-    pub status: bool,     // 1
-    pub amount: u32,      // 4
-    pub reciever: Pubkey, // 32
-    pub timestamp: i64,   // 8
-    pub votes: u32,       // 4
+
+const VOTER_ESCROW_SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 1;
+
+// Escrows are derived from the mint as well, so a new governance mint gets new escrows and the
+// tokens of the old mint can still be withdrawn
+#[account]
+pub struct VoterEscrow {
+    pub project: Pubkey,   // 32
+    pub voter: Pubkey,     // 32
+    pub mint: Pubkey,      // 32
+    pub amount: u64,       // 8
+    pub locked_until: i64, // 8
+    pub bump: u8,          // 1
 }
-#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
-pub struct Shutdown {
-    pub status: bool,   // 1
-    pub timestamp: i64, // 8
-    pub votes: u32,     // 4
+
+const VOTE_RECORD_SPACE: usize = 8 + 32 + 32 + 8 + 1 + 1;
+
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey, // 32
+    pub voter: Pubkey,    // 32
+    pub weight: u64,      // 8
+    pub approve: bool,    // 1
+    pub bump: u8,         // 1
 }
 
+// A new project holds the creator as its only signatory, empty vectors and no options set. The
+// account grows with its data from there
+const PROJECT_INITIAL_SPACE: usize = 8 + 32 + (4 + 70) + 4 + 4 + 4 + 4 + 1 + 4 + 4 + 32 + 1 + 8
+    + 4 + 1 + 4 + 1 + 4 + 8 + 4 + 4 + 4 + 4 + 1 + 8 + 4;
+
 #[account]
 pub struct ProjectParameter {
-    pub authority: Pubkey,                  // 32
-    pub signatories: Vec<Signature>,        // 38 * n
-    pub add: AddSignatory,                  // 41
-    pub delete: DeleteSignatory,            // 41
-    pub change_threshold: ChangeThreshold,  // 13
-    pub change_time_limit: ChangeTimeLimit, // 13
-    pub transfer_amount: TransferAmount,    // 45
-    pub shutdown: Shutdown,                 // 13
-    pub threshold: u32,                     // 4
-    pub time_limit: u32,                    // 4
-    pub last_tx: i32,                       // 4
-    pub staked_amount: u32,                 // 4
-    pub percent_transfer: u8,               // 1
-    pub last_reduced_threshold: i32,        //4
-    pub approval: u32,                      //4
-    pub token_mint: Pubkey,                 // 32
+    pub authority: Pubkey,                         // 32
+    pub signatories: Vec<Signature>,               // 4 + 70 * n
+    pub threshold: u32,                            // 4
+    pub time_limit: u32,                           // 4
+    pub last_tx: i32,                              // 4
+    pub staked_amount: u32,                        // 4
+    pub percent_transfer: u8,                      // 1
+    pub last_reduced_threshold: i32,               //4
+    pub approval: u32,                             //4
+    pub token_mint: Pubkey,                        // 32
+    pub shutdown: bool,                            // 1
+    pub proposal_count: u64,                       // 8
+    pub execution_delay: u32,                      // 4
+    pub closed: bool,                              // 1
+    pub kind_thresholds: Vec<KindThreshold>,       // 4 + 5 * n
+    pub threshold_policy: Option<ThresholdPolicy>, // 1 + 9
+    pub amount_tiers: Vec<AmountTier>,             // 4 + 48 * n
+    pub config_generation: u64,                    // 8
+    pub delegations: Vec<Delegation>,              // 4 + 74 * n
+    pub guardians: Vec<Pubkey>,                    // 4 + 32 * 5
+    pub guardian_threshold: u32,                   // 4
+    pub recovery_delay: u32,                       // 4
+    pub governance_mint: Option<Pubkey>,           // 1 + 32
+    pub token_threshold: u64,                      // 8
+    pub max_members: u32,                          // 4
 }
 
 impl ProjectParameter {
@@ -783,115 +2656,757 @@ impl ProjectParameter {
 
         index
     }
-    pub fn reset_add(&mut self) {
-        self.add.votes = 0;
-        self.add.status = false;
-        self.add.timestamp = 0;
-        self.add.new_signatory = Vec::new();
 
-        for i in 0..self.signatories.len() {
-            self.signatories[i].add = false;
+    // Every member allowed to propose can put forward proposals, and so can the project authority
+    // as long as it is not a member. An authority that is a member needs the permission like any
+    // other. In token mode the members no longer govern, so anyone can propose and pays for it
+    pub fn can_propose(&self, key: Pubkey) -> bool {
+        if self.governance_mint.is_some() {
+            return true;
+        }
+        if self.get_index(key) == usize::MAX {
+            return key == self.authority;
+        }
+        self.has_permission(key, PERMISSION_PROPOSE)
+    }
+
+    pub fn has_permission(&self, key: Pubkey, permission: u8) -> bool {
+        let index = self.get_index(key);
+        index != usize::MAX && self.signatories[index].permissions & permission == permission
+    }
+
+    pub fn check_permission(&self, key: Pubkey, permission: u8) -> Result<()> {
+        if self.get_index(key) == usize::MAX {
+            return Err(error!(ErrorCode::InvalidSigner));
         }
+        if !self.has_permission(key, permission) {
+            return Err(error!(ErrorCode::MissingPermission));
+        }
+        Ok(())
     }
-    pub fn create_add(&mut self, signatories: Vec<Pubkey>) {
-        self.add.status = true;
-        self.add.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.add.votes = 0;
-        for i in 0..signatories.len() {
-            self.add.new_signatory.push(signatories[i]);
+
+    // Voters pass their slot, so finding them takes the same time however many members there are
+    pub fn vote_weight(&self, key: Pubkey, slot: u32) -> Result<u64> {
+        let member = match self.signatories.get(slot as usize) {
+            Some(member) if member.key == key => member,
+            _ => return Err(error!(ErrorCode::InvalidSigner)),
+        };
+        if member.permissions & PERMISSION_VOTE != PERMISSION_VOTE {
+            return Err(error!(ErrorCode::MissingPermission));
         }
+        Ok(u64::from(member.weight))
     }
 
-    pub fn create_delete(&mut self, signatories: Vec<Pubkey>) {
-        self.delete.status = true;
-        self.delete.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.delete.votes = 0;
-        for i in 0..signatories.len() {
-            self.delete.old_signatory.push(signatories[i]);
+    // A delegation without a kind covers every kind of proposal
+    pub fn is_delegate(&self, delegator: Pubkey, delegate: Pubkey, kind: ProposalKind) -> bool {
+        let now = Clock::get().unwrap().unix_timestamp;
+        self.delegations.iter().any(|delegation| {
+            delegation.delegator == delegator
+                && delegation.delegate == delegate
+                && (delegation.kind.is_none() || delegation.kind == Some(kind))
+                && delegation.is_active(now)
+        })
+    }
+
+    pub fn previous_key(&self, key: Pubkey) -> Pubkey {
+        let index = self.get_index(key);
+        if index == usize::MAX {
+            return Pubkey::default();
         }
+        self.signatories[index].previous_key
     }
 
-    pub fn reset_delete(&mut self) {
-        self.delete.votes = 0;
-        self.delete.status = false;
-        self.delete.timestamp = 0;
-        self.delete.old_signatory = Vec::new();
+    // The member keeps their slot, permissions, weight, delegations and votes under the new key
+    pub fn rotate_key(&mut self, old_key: Pubkey, new_key: Pubkey) -> Result<()> {
+        let index = self.get_index(old_key);
+        if index == usize::MAX {
+            return Err(error!(ErrorCode::SignatoryNotFound));
+        }
+        if self.get_index(new_key) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
+        }
 
-        for i in 0..self.signatories.len() {
-            self.signatories[i].delete = false;
+        self.signatories[index].key = new_key;
+        self.signatories[index].previous_key = old_key;
+        if self.authority == old_key {
+            self.authority = new_key;
+        }
+        for delegation in self.delegations.iter_mut() {
+            if delegation.delegator == old_key {
+                delegation.delegator = new_key;
+            }
+            if delegation.delegate == old_key {
+                delegation.delegate = new_key;
+            }
         }
+        Ok(())
     }
 
-    pub fn create_change(&mut self, threshold: u32) {
-        self.change_threshold.status = true;
-        self.change_threshold.new_threshold = threshold;
-        self.change_threshold.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.change_threshold.votes = 0;
-        self.approval = self.threshold;
+    // In token mode the approving deposits have to reach the token threshold instead
+    pub fn required_weight(&self, proposal: &Proposal) -> u64 {
+        if self.governance_mint.is_some() {
+            return self.token_threshold;
+        }
+        u64::from(self.required_votes(proposal))
     }
 
-    pub fn reset_change(&mut self) {
-        self.change_threshold.status = false;
-        self.change_threshold.new_threshold = 0;
-        self.change_threshold.timestamp = 0;
-        self.change_threshold.votes = 0;
-        self.last_reduced_threshold = 0;
-        self.shutdown = false;
+    // Thresholds are expressed in voting weight, members without the vote permission do not count
+    pub fn total_weight(&self) -> u32 {
+        self.signatories
+            .iter()
+            .filter(|signatory| signatory.permissions & PERMISSION_VOTE != 0)
+            .map(|signatory| signatory.weight)
+            .sum()
+    }
 
-        for i in 0..self.signatories.len() {
-            self.signatories[i].change_threshold = false;
+    pub fn required_votes(&self, proposal: &Proposal) -> u32 {
+        let action = &proposal.action;
+        // instructions can move the pool without going through the tiers, so they need the
+        // strictest threshold of the project
+        if action.invokes_instructions() {
+            return self.kind_threshold(action.kind()).max(self.strictest_threshold());
+        }
+        match action {
+            // the approvals needed to change the threshold drop while the project is inactive,
+            // each proposal keeps the approvals it was created with
+            ProposalAction::ChangeThreshold { .. } => proposal.required_votes,
+            // the threshold tables can only be changed by the strictest threshold in them
+            ProposalAction::ChangeKindThresholds { .. }
+            | ProposalAction::ChangeAmountTiers { .. } => self.strictest_threshold(),
+            // switching to a percentage changes the threshold, so it needs as many votes
+            ProposalAction::ChangeThresholdPolicy { .. } => self
+                .kind_threshold(ProposalKind::ChangeThresholdPolicy)
+                .max(self.kind_threshold(ProposalKind::ChangeThreshold)),
+            // a batch needs the threshold of every kind of action it contains
+            ProposalAction::Batch { actions } => {
+                let required = self
+                    .kind_threshold(ProposalKind::Batch)
+                    .max(self.tier_threshold(action.transfer_amount()));
+                actions
+                    .iter()
+                    .map(|action| self.kind_threshold(action.kind()))
+                    .fold(required, u32::max)
+            }
+            ProposalAction::TransferAmount { .. } => self
+                .kind_threshold(ProposalKind::TransferAmount)
+                .max(self.tier_threshold(action.transfer_amount())),
+            _ => self.kind_threshold(action.kind()),
+        }
+    }
+
+    pub fn required_delay(&self, action: &ProposalAction) -> u32 {
+        if action.invokes_instructions() {
+            let tiers = self.amount_tiers.iter().filter(|tier| tier.mint == self.token_mint);
+            return tiers.map(|tier| tier.delay).fold(self.execution_delay, u32::max);
+        }
+        match self.amount_tier(action.transfer_amount()) {
+            Some(tier) => self.execution_delay.max(tier.delay),
+            None => self.execution_delay,
+        }
+    }
+
+    // The tier with the highest minimum the amount reaches for the mint of the project pool
+    pub fn amount_tier(&self, amount: u64) -> Option<&AmountTier> {
+        let mut found: Option<&AmountTier> = None;
+        if amount == 0 {
+            return found;
+        }
+        for tier in &self.amount_tiers {
+            if tier.mint != self.token_mint || tier.min_amount > amount {
+                continue;
+            }
+            match found {
+                Some(current) if current.min_amount >= tier.min_amount => {}
+                _ => found = Some(tier),
+            }
         }
+        found
     }
 
-    pub fn reduce_approval(&mut self, threshold: u32) {
-        self.change_threshold.status = true;
-        self.change_threshold.new_threshold = threshold;
-        self.change_threshold.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.change_threshold.votes = 0;
+    pub fn tier_threshold(&self, amount: u64) -> u32 {
+        match self.amount_tier(amount) {
+            Some(tier) => {
+                // the fall back lowers every threshold along with the default one
+                if self.shutdown {
+                    return tier.threshold.min(self.threshold);
+                }
+                tier.threshold
+            }
+            None => 0,
+        }
+    }
+
+    pub fn validate_amount_tiers(&self, tiers: &[AmountTier]) -> Result<()> {
+        if tiers.len() > MAX_AMOUNT_TIERS {
+            return Err(error!(ErrorCode::TooManyAmountTiers));
+        }
+        let total_weight = self.total_weight();
+        for (i, tier) in tiers.iter().enumerate() {
+            // only transfers out of the project pool are checked against the tiers
+            if tier.mint != self.token_mint {
+                return Err(error!(ErrorCode::InvalidTierMint));
+            }
+            if tier.threshold == 0 || tier.threshold > total_weight {
+                return Err(error!(ErrorCode::ThresholdIsMore));
+            }
+            if tier.delay > 60 * 60 * 24 * 30 {
+                return Err(error!(ErrorCode::ExecutionDelayMore));
+            }
+            if tiers[..i].iter().any(|other| other.min_amount == tier.min_amount) {
+                return Err(error!(ErrorCode::DuplicateAmountTier));
+            }
+        }
+        Ok(())
     }
 
-    pub fn create_time_limit(&mut self, time_limit: u32) {
-        self.change_time_limit.status = true;
-        self.change_time_limit.new_time_limit = time_limit;
-        self.change_time_limit.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.change_time_limit.votes = 0;
+    pub fn kind_threshold(&self, kind: ProposalKind) -> u32 {
+        match self.kind_thresholds.iter().find(|entry| entry.kind == kind) {
+            // the fall back lowers every threshold along with the default one
+            Some(entry) if self.shutdown => entry.threshold.min(self.threshold),
+            Some(entry) => entry.threshold,
+            None => self.threshold,
+        }
     }
 
-    pub fn reset_time_limit(&mut self) {
-        self.change_time_limit.status = false;
-        self.change_time_limit.new_time_limit = 0;
-        self.change_time_limit.timestamp = 0;
-        self.change_time_limit.votes = 0;
+    pub fn strictest_threshold(&self) -> u32 {
+        let kinds = self.kind_thresholds.iter().map(|entry| self.kind_threshold(entry.kind));
+        let tiers = self.amount_tiers.iter().map(|tier| tier.threshold);
+        kinds.chain(tiers).fold(self.threshold, u32::max)
+    }
 
-        for i in 0..self.signatories.len() {
-            self.signatories[i].change_threshold = false;
+    pub fn validate_kind_thresholds(&self, thresholds: &[KindThreshold]) -> Result<()> {
+        let total_weight = self.total_weight();
+        for (i, entry) in thresholds.iter().enumerate() {
+            if entry.threshold > total_weight {
+                return Err(error!(ErrorCode::ThresholdIsMore));
+            }
+            if thresholds[..i].iter().any(|other| other.kind == entry.kind) {
+                return Err(error!(ErrorCode::DuplicateProposalKind));
+            }
         }
+        Ok(())
     }
 
-    pub fn create_transfer_amount(&mut self, amount: u32, reciever: Pubkey) {
-        self.transfer_amount.status = true;
-        self.transfer_amount.amount = amount;
-        self.transfer_amount.reciever = reciever;
-        self.transfer_amount.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.transfer_amount.votes = 0;
+    // A threshold of 0 removes the entry so the kind falls back to the default threshold
+    pub fn set_kind_thresholds(&mut self, thresholds: &[KindThreshold]) {
+        for threshold in thresholds {
+            self.kind_thresholds.retain(|entry| entry.kind != threshold.kind);
+            if threshold.threshold > 0 {
+                self.kind_thresholds.push(threshold.clone());
+            }
+        }
     }
 
-    pub fn reset_transfer_amount(&mut self) {
-        self.transfer_amount.status = false;
-        self.transfer_amount.amount = 0;
-        self.transfer_amount.timestamp = 0;
-        self.transfer_amount.votes = 0;
-        for i in 0..self.signatories.len() {
-            self.signatories[i].transfer_amount = false;
+    // The thresholds have to stay reachable and someone has to be left to execute proposals
+    pub fn validate_permissions(&self, index: usize, permissions: u8) -> Result<()> {
+        if permissions & !PERMISSION_ALL != 0 {
+            return Err(error!(ErrorCode::InvalidPermissions));
+        }
+
+        let signatory = &self.signatories[index];
+        let mut total_weight = self.total_weight();
+        if signatory.permissions & PERMISSION_VOTE != 0 {
+            total_weight -= signatory.weight;
+        }
+        if permissions & PERMISSION_VOTE != 0 {
+            total_weight += signatory.weight;
         }
+        if self.strictest_threshold() > total_weight {
+            return Err(error!(ErrorCode::ThresholdIsMore));
+        }
+
+        let other_executor = self.signatories.iter().enumerate().any(|(i, other)| {
+            i != index && other.permissions & PERMISSION_EXECUTE != 0
+        });
+        if permissions & PERMISSION_EXECUTE == 0 && !other_executor {
+            return Err(error!(ErrorCode::NoExecutor));
+        }
+        Ok(())
     }
-    pub fn create_shutdown(&mut self) {
-        self.status = true;
-        self.timestamp = Clock::get().unwrap().unix_timestamp;
-        self.votes = 0;
+
+    pub fn validate_weight(&self, index: usize, weight: u32) -> Result<()> {
+        if weight == 0 || weight > MAX_WEIGHT {
+            return Err(error!(ErrorCode::InvalidWeight));
+        }
+
+        let signatory = &self.signatories[index];
+        let mut total_weight = self.total_weight();
+        if signatory.permissions & PERMISSION_VOTE != 0 {
+            total_weight = total_weight - signatory.weight + weight;
+        }
+        if self.strictest_threshold() > total_weight {
+            return Err(error!(ErrorCode::ThresholdIsMore));
+        }
+        Ok(())
+    }
+
+    pub fn validate_max_members(&self, max_members: u32) -> Result<()> {
+        if max_members == 0
+            || max_members > MAX_MEMBERS
+            || (max_members as usize) < self.signatories.len()
+        {
+            return Err(error!(ErrorCode::InvalidMaxMembers));
+        }
+        Ok(())
+    }
+
+    // The checks shared by every proposal that adds members, on its own or inside a batch
+    pub fn validate_new_signatories(
+        &self,
+        project: Pubkey,
+        new_signatory: &[Pubkey],
+    ) -> Result<()> {
+        if new_signatory.len() > MAX_SIGNATORIES_PER_PROPOSAL {
+            return Err(error!(ErrorCode::TooManyNewSignatories));
+        }
+        if self.signatories.len() + new_signatory.len() > self.max_members as usize {
+            return Err(error!(ErrorCode::TooManyMembers));
+        }
+        for (i, key) in new_signatory.iter().enumerate() {
+            if self.get_index(*key) != usize::MAX {
+                return Err(error!(ErrorCode::SignatoryAlreadyExists));
+            }
+            if *key == project {
+                return Err(error!(ErrorCode::InvalidChildProject));
+            }
+            if new_signatory[..i].contains(key) {
+                return Err(error!(ErrorCode::DuplicateSignatory));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_old_signatories(&self, old_signatory: &[Pubkey]) -> Result<()> {
+        if old_signatory.len() > MAX_SIGNATORIES_PER_PROPOSAL {
+            return Err(error!(ErrorCode::TooManyNewSignatories));
+        }
+        if old_signatory.len() >= self.signatories.len() {
+            return Err(error!(ErrorCode::CannotRemoveSignatory));
+        }
+        for (i, key) in old_signatory.iter().enumerate() {
+            if self.get_index(*key) == usize::MAX {
+                return Err(error!(ErrorCode::SignatoryNotFound));
+            }
+            if old_signatory[..i].contains(key) {
+                return Err(error!(ErrorCode::DuplicateSignatory));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_transfer_amount(&self, amount: u64) -> Result<()> {
+        if amount > self.staked_amount as u64 {
+            return Err(error!(ErrorCode::InsufficientBalance));
+        }
+        Ok(())
+    }
+
+    // A batch is checked as a whole: its members and transfers add up across all of its actions
+    pub fn validate_batch(&self, project: Pubkey, actions: &[BatchAction]) -> Result<()> {
+        if actions.is_empty() {
+            return Err(error!(ErrorCode::EmptyBatch));
+        }
+
+        let mut new_signatories = Vec::new();
+        let mut old_signatories = Vec::new();
+        for action in actions {
+            match action {
+                BatchAction::AddSignatory { new_signatory } => {
+                    new_signatories.extend_from_slice(new_signatory)
+                }
+                BatchAction::DeleteSignatory { old_signatory } => {
+                    old_signatories.extend_from_slice(old_signatory)
+                }
+                _ => {}
+            }
+        }
+        if !new_signatories.is_empty() {
+            self.validate_new_signatories(project, &new_signatories)?;
+        }
+        if !old_signatories.is_empty() {
+            self.validate_old_signatories(&old_signatories)?;
+        }
+
+        self.validate_transfer_amount(BatchAction::total_amount(actions))
+    }
+
+    pub fn add_signatories(&mut self, new_signatory: &[Pubkey], weights: &[u32]) -> Result<()> {
+        if self.signatories.len() + new_signatory.len() > self.max_members as usize {
+            return Err(error!(ErrorCode::TooManyMembers));
+        }
+        for (i, key) in new_signatory.iter().enumerate() {
+            if self.get_index(*key) != usize::MAX {
+                return Err(error!(ErrorCode::SignatoryAlreadyExists));
+            }
+            let weight = weights.get(i).copied().unwrap_or(1);
+            let sig = Signature {
+                key: *key,
+                permissions: PERMISSION_ALL,
+                weight,
+                previous_key: Pubkey::default(),
+                is_project: false,
+            };
+            self.signatories.push(sig);
+        }
+        self.apply_threshold_policy();
+        Ok(())
+    }
+
+    // A child that already has projects among its members is refused, so however deep the
+    // projects nest, the last link of a cycle could never be added
+    pub fn validate_child_project(
+        &self,
+        project: Pubkey,
+        child_key: Pubkey,
+        child: &ProjectParameter,
+    ) -> Result<()> {
+        if self.get_index(child_key) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
+        }
+        if child_key == project || child.signatories.iter().any(|member| member.is_project) {
+            return Err(error!(ErrorCode::InvalidChildProject));
+        }
+        Ok(())
+    }
+
+    // The child project votes by executing its own proposals that call into this program
+    pub fn add_project_signatory(&mut self, project: Pubkey) -> Result<()> {
+        if self.get_index(project) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
+        }
+        if self.signatories.len() >= self.max_members as usize {
+            return Err(error!(ErrorCode::TooManyMembers));
+        }
+        let sig = Signature {
+            key: project,
+            permissions: PERMISSION_ALL,
+            weight: 1,
+            previous_key: Pubkey::default(),
+            is_project: true,
+        };
+        self.signatories.push(sig);
+        self.apply_threshold_policy();
+        Ok(())
+    }
+
+    pub fn apply_threshold_policy(&mut self) {
+        if let Some(policy) = &self.threshold_policy {
+            let threshold = policy.threshold(self.total_weight());
+            msg!("the threshold is now {}", threshold);
+            self.threshold = threshold;
+            self.approval = threshold;
+        }
+    }
+
+    pub fn remove_signatories(&mut self, old_signatory: &[Pubkey]) -> Result<()> {
+        let mut all_index = Vec::new();
+
+        for key in old_signatory {
+            let index = self.get_index(*key);
+            if index == usize::MAX {
+                return Err(error!(ErrorCode::SignatoryNotFound));
+            } else {
+                all_index.push(index);
+            }
+        }
+
+        if all_index.len() >= self.signatories.len() {
+            return Err(error!(ErrorCode::CannotRemoveSignatory));
+        }
+
+        all_index.sort();
+
+        for (removed, index) in all_index.iter().enumerate() {
+            self.signatories.remove(index - removed);
+        }
+        self.delegations.retain(|delegation| !old_signatory.contains(&delegation.delegator));
+        self.clamp_thresholds();
+        self.apply_threshold_policy();
+        Ok(())
+    }
+
+    // The new member takes over the slot, permissions and weight, so no threshold moves
+    pub fn replace_signatory(&mut self, old_key: Pubkey, new_key: Pubkey) -> Result<()> {
+        let index = self.get_index(old_key);
+        if index == usize::MAX {
+            return Err(error!(ErrorCode::SignatoryNotFound));
+        }
+        if self.get_index(new_key) != usize::MAX {
+            return Err(error!(ErrorCode::SignatoryAlreadyExists));
+        }
+
+        self.signatories[index].key = new_key;
+        self.signatories[index].previous_key = Pubkey::default();
+        // the new key was passed as no project, and a project joining as a plain member cannot
+        // act in this project
+        self.signatories[index].is_project = false;
+        self.delegations.retain(|delegation| delegation.delegator != old_key);
+        Ok(())
+    }
+
+    // No threshold can be more than the weight left to vote with
+    pub fn clamp_thresholds(&mut self) {
+        let total_weight = self.total_weight();
+        if self.threshold > total_weight {
+            self.threshold = total_weight;
+        }
+        for entry in self.kind_thresholds.iter_mut() {
+            entry.threshold = entry.threshold.min(total_weight);
+        }
+        for tier in self.amount_tiers.iter_mut() {
+            tier.threshold = tier.threshold.min(total_weight);
+        }
+    }
+}
+
+fn cast_vote(
+    parameters: &ProjectParameter,
+    proposal: &mut Proposal,
+    voter: Pubkey,
+    slot: u32,
+    kind: ProposalKind,
+) -> Result<()> {
+    if parameters.governance_mint.is_some() {
+        return Err(error!(ErrorCode::TokenVotingEnabled));
+    }
+
+    let weight = parameters.vote_weight(voter, slot)?;
+    check_open_for_votes(parameters, proposal, kind)?;
+
+    proposal.sign(slot as usize, weight)?;
+    approve_if_reached(parameters, proposal);
+
+    Ok(())
+}
+
+fn check_open_for_votes(
+    parameters: &ProjectParameter,
+    proposal: &Proposal,
+    kind: ProposalKind,
+) -> Result<()> {
+    if proposal.state != ProposalState::Active {
+        return Err(error!(ErrorCode::NoProposalCreated));
+    }
+
+    if proposal.is_expired() {
+        return Err(error!(ErrorCode::ProposalExpired));
+    }
+
+    if proposal.is_stale(parameters.config_generation) {
+        return Err(error!(ErrorCode::StaleProposal));
+    }
+
+    // Signers state the kind they think they are approving so that a mix up fails loudly
+    if proposal.action.kind() != kind {
+        return Err(error!(ErrorCode::WrongProposalKind));
+    }
+
+    Ok(())
+}
+
+fn approve_if_reached(parameters: &ProjectParameter, proposal: &mut Proposal) {
+    if proposal.approval_weight >= parameters.required_weight(proposal) {
+        msg!("the proposal has been approved");
+        proposal.approve(parameters.required_delay(&proposal.action));
+    }
+}
+
+// Once the remaining voting power cannot reach the required weight the proposal can never pass.
+// Token holders can still deposit more, so there the rejections have to reach the threshold
+fn reject_if_unreachable(parameters: &ProjectParameter, proposal: &mut Proposal) {
+    let rejected = if parameters.governance_mint.is_some() {
+        proposal.rejection_weight >= parameters.token_threshold
+    } else {
+        let remaining = proposal.voting_weight.saturating_sub(proposal.rejection_weight);
+        remaining < parameters.required_weight(proposal)
+    };
+    if rejected {
+        msg!("the proposal has been rejected");
+        proposal.state = ProposalState::Rejected;
+    }
+}
+
+// Keeps the project account as large as its data. The payer covers the rent of any growth and
+// receives the rent freed by any shrink, other lamports held by the project are left alone.
+fn resize_project<'info>(
+    parameters: &Account<'info, ProjectParameter>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<()> {
+    let project_info = parameters.to_account_info();
+    let old_len = project_info.data_len();
+    let mut counter = ByteCounter(0);
+    parameters.serialize(&mut counter)?;
+    let new_len = 8 + counter.0;
+    if new_len == old_len {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?;
+    let minimum_balance = rent.minimum_balance(new_len);
+    if new_len > old_len {
+        if minimum_balance > project_info.lamports() {
+            let cpi_ctx = CpiContext::new(
+                system_program,
+                anchor_lang::system_program::Transfer {
+                    from: payer,
+                    to: project_info.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(
+                cpi_ctx,
+                minimum_balance - project_info.lamports(),
+            )?;
+        }
+    } else {
+        let refund = rent
+            .minimum_balance(old_len)
+            .saturating_sub(minimum_balance)
+            .min(project_info.lamports().saturating_sub(minimum_balance));
+        **project_info.try_borrow_mut_lamports()? -= refund;
+        **payer.try_borrow_mut_lamports()? += refund;
+    }
+    project_info.realloc(new_len, false)?;
+
+    Ok(())
+}
+
+// Measures the serialized project without writing a copy of it to the heap
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Slots past the bitmap, including usize::MAX for keys that are not members, are never set
+fn slot_is_set(bitmap: &[u8; VOTE_BITMAP_SIZE], slot: usize) -> bool {
+    slot < VOTE_BITMAP_SIZE * 8 && bitmap[slot / 8] & (1 << (slot % 8)) != 0
+}
+
+fn set_slot(bitmap: &mut [u8; VOTE_BITMAP_SIZE], slot: usize, value: bool) {
+    if value {
+        bitmap[slot / 8] |= 1 << (slot % 8);
+    } else {
+        bitmap[slot / 8] &= !(1 << (slot % 8));
+    }
+}
+
+// Projects can only become members through a child project proposal, which checks for cycles,
+// so every other new member is passed in to show it is not an account of this program
+fn check_not_projects(
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+    new_signatory: &[Pubkey],
+) -> Result<()> {
+    for key in new_signatory {
+        if find_account(accounts, key)?.owner == program_id {
+            return Err(error!(ErrorCode::InvalidChildProject));
+        }
+    }
+    Ok(())
+}
+
+fn find_account<'info>(
+    accounts: &[AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<AccountInfo<'info>> {
+    match accounts.iter().find(|account| account.key == key) {
+        Some(account) => Ok(account.clone()),
+        None => Err(error!(ErrorCode::MissingAccount)),
     }
 }
 
+fn execute_transfer<'info>(
+    parameters: &mut Account<'info, ProjectParameter>,
+    remaining_accounts: &[AccountInfo<'info>],
+    pool: &Pubkey,
+    reciever: &Pubkey,
+    signer: &[&[&[u8]]],
+    amount: u32,
+) -> Result<()> {
+    if !parameters.shutdown && parameters.threshold == 1 {
+        return Err(error!(ErrorCode::CannotTransferDueToLowThreshold));
+    }
+
+    if amount > parameters.staked_amount {
+        return Err(error!(ErrorCode::InsufficientBalance));
+    }
+
+    transfer_from_pool(
+        find_account(remaining_accounts, &anchor_spl::token::ID)?,
+        find_account(remaining_accounts, pool)?,
+        find_account(remaining_accounts, reciever)?,
+        parameters.to_account_info(),
+        signer,
+        amount as u64,
+    )?;
+    parameters.staked_amount -= amount;
+
+    Ok(())
+}
+
+fn transfer_from_pool<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    // Below is the actual instruction that we are going to send to the Token program.
+    let transfer_instruction = Transfer {
+        from,
+        to,
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program,
+        transfer_instruction,
+        signer, //signer PDA
+    );
+
+    anchor_spl::token::transfer(cpi_ctx, amount)
+}
+
+fn invoke_instruction<'info>(
+    instruction: &ProposalInstruction,
+    remaining_accounts: &[AccountInfo<'info>],
+    project: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    // The accounts of the instruction along with the program being invoked are
+    // passed as remaining accounts, the project itself is always available to sign.
+    let mut account_infos = remaining_accounts.to_vec();
+    account_infos.push(project.clone());
+
+    // A project that joined another one as a plain member, for example before it was created,
+    // is not covered by the cycle guard, so it cannot act in that project at all
+    if instruction.program_id == crate::ID {
+        if let Some(target) = instruction.accounts.first() {
+            let info = find_account(&account_infos, &target.pubkey)?;
+            if info.owner == &crate::ID {
+                let data = info.data.borrow();
+                if let Ok(target) = ProjectParameter::try_deserialize(&mut &data[..]) {
+                    let mut members = target.signatories.iter();
+                    if members.any(|member| member.key == *project.key && !member.is_project) {
+                        return Err(error!(ErrorCode::InvalidChildProject));
+                    }
+                }
+            }
+        }
+    }
+
+    invoke_signed(&instruction.to_instruction(), &account_infos, signer)?;
+    Ok(())
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid Signer")]
@@ -912,8 +3427,6 @@ pub enum ErrorCode {
     RepeatedSignature,
     #[msg("There is no proposal to sign")]
     NoProposalCreated,
-    #[msg("There is already a proposal in progress")]
-    ProposalInProgress,
     #[msg("This signatory does not exist")]
     SignatoryNotFound,
     #[msg("The project is still alive")]
@@ -929,5 +3442,95 @@ pub enum ErrorCode {
     #[msg("The transfer cannot be completed if there is only 1 signatory, add more signatories and you can complete the transfer")]
     CannotTransferDueToLowThreshold,
     #[msg("The shutdown can be done only when the project wallet doesnt have any funds ")]
-    EmptyFundsBeforeClosing
+    EmptyFundsBeforeClosing,
+    #[msg("The proposal does not belong to this project")]
+    InvalidProposal,
+    #[msg("The proposal is not of the kind being signed")]
+    WrongProposalKind,
+    #[msg("The time limit for this proposal has passed")]
+    ProposalExpired,
+    #[msg("A batch proposal needs at least one action")]
+    EmptyBatch,
+    #[msg("An account needed to execute the proposal was not passed")]
+    MissingAccount,
+    #[msg("The proposal is no longer a draft")]
+    ProposalNotDraft,
+    #[msg("You have not signed this proposal")]
+    NoVoteToRevoke,
+    #[msg("The proposal has not been approved")]
+    ProposalNotApproved,
+    #[msg("The execution delay cannot be more than 30 days")]
+    ExecutionDelayMore,
+    #[msg("The execution delay of the proposal has not passed yet")]
+    TimelockNotPassed,
+    #[msg("The proposal can no longer be objected to")]
+    ObjectionWindowPassed,
+    #[msg("The proposal title cannot be more than 64 characters")]
+    TitleTooLong,
+    #[msg("The proposal description uri cannot be more than 200 characters")]
+    DescriptionUriTooLong,
+    #[msg("The project has been shut down")]
+    ProjectClosed,
+    #[msg("The proposal has not expired yet")]
+    ProposalNotExpired,
+    #[msg("Only executed, rejected, expired or cancelled proposals can be closed")]
+    ProposalNotFinished,
+    #[msg("A proposal kind can only be given one threshold")]
+    DuplicateProposalKind,
+    #[msg("The percentage has to be between 1 and 100 and the floor between 1 and the ceiling")]
+    InvalidThresholdPolicy,
+    #[msg("A project can have at most 10 amount tiers")]
+    TooManyAmountTiers,
+    #[msg("Two amount tiers cannot have the same mint and minimum amount")]
+    DuplicateAmountTier,
+    #[msg("The members or thresholds of the project changed since the proposal was created")]
+    StaleProposal,
+    #[msg("The signatory does not have the permission for this action")]
+    MissingPermission,
+    #[msg("Unknown permission flags")]
+    InvalidPermissions,
+    #[msg("Weights have to be between 1 and 1000000, with one weight for every new signatory")]
+    InvalidWeight,
+    #[msg("Votes can only be delegated to another key and until a time in the future")]
+    InvalidDelegation,
+    #[msg("A member can have 3 delegations and a project 50, wait for some to expire or revoke them")]
+    TooManyDelegations,
+    #[msg("No delegation of this kind to revoke")]
+    DelegationNotFound,
+    #[msg("The signer has no active delegation for this proposal kind")]
+    NotDelegate,
+    #[msg("A project can only join as a child project, and only while no other project is its member")]
+    InvalidChildProject,
+    #[msg("Up to 5 distinct guardians with a threshold they can reach and a recovery delay of at least 7 days")]
+    InvalidGuardians,
+    #[msg("Only a guardian of the project can do this")]
+    NotGuardian,
+    #[msg("The recovery has not been approved by enough guardians")]
+    RecoveryNotApproved,
+    #[msg("A governance mint needs a token threshold, and going back to signatories needs none")]
+    InvalidGovernance,
+    #[msg("The mint is not the governance mint of the project")]
+    InvalidGovernanceMint,
+    #[msg("This project votes with governance token escrows instead of signatories")]
+    TokenVotingEnabled,
+    #[msg("This project votes with signatories, it has no governance mint")]
+    TokenVotingDisabled,
+    #[msg("The escrow is locked until the proposals it voted on have expired")]
+    EscrowLocked,
+    #[msg("The escrow does not hold enough governance tokens")]
+    InsufficientDeposit,
+    #[msg("The project has reached its maximum number of members, it can be raised by a proposal")]
+    TooManyMembers,
+    #[msg("The maximum number of members has to be between the current members and 112")]
+    InvalidMaxMembers,
+    #[msg("A proposal can add or remove at most 10 signatories")]
+    TooManyNewSignatories,
+    #[msg("A signatory can only appear once in a proposal")]
+    DuplicateSignatory,
+    #[msg("Amount tiers can only be set for the mint of the project pool")]
+    InvalidTierMint,
+    #[msg("At least one signatory has to keep the permission to execute proposals")]
+    NoExecutor,
+    #[msg("The initial signatories have already been added")]
+    SignatoriesAlreadyAdded,
 }
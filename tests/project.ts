@@ -6,6 +6,7 @@ const assert = require("assert");
 import * as spl from "@solana/spl-token";
 import bs58 from "bs58";
 import { v4 as uuidv4 } from "uuid";
+import { createHash } from "crypto";
 
 describe("project", () => {
   // Configure the client to use the local cluster.
@@ -172,6 +173,139 @@ describe("project", () => {
 
   console.log(projectId);
 
+  const getProposalPDA = async (
+    projectPDA: anchor.web3.PublicKey,
+    index: number
+  ) =>
+    await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("proposal"),
+        projectPDA.toBuffer(),
+        new anchor.BN(index).toArrayLike(Buffer, "le", 8),
+      ],
+      projectProgram.programId
+    );
+
+  const proposalMetadata = (title: string) => {
+    const descriptionUri = `https://example.com/proposals/${encodeURIComponent(
+      title
+    )}`;
+    return {
+      title,
+      descriptionUri,
+      descriptionHash: Array.from(
+        createHash("sha256").update(descriptionUri).digest()
+      ),
+    };
+  };
+
+  // Keys joining a project are passed on execution so the program can check they are no projects
+  const memberAccounts = (keys: anchor.web3.PublicKey[]) =>
+    keys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+
+  // Members vote with their slot, tests that vote with a key that is not a member pass the slot themselves
+  const memberSlot = async (
+    project: anchor.web3.PublicKey,
+    key: anchor.web3.PublicKey
+  ) => {
+    const state = await projectProgram.account.projectParameter.fetch(project);
+    const slot = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == key.toBase58()
+    );
+    if (slot == -1) throw `${key.toBase58()} is not a member`;
+    return slot;
+  };
+
+  // Member permissions as stored in the project
+  const PROPOSE = 1;
+  const VOTE = 2;
+  const EXECUTE = 4;
+  const CANCEL = 8;
+  const VETO = 16;
+  const ALL = PROPOSE | VOTE | EXECUTE | CANCEL | VETO;
+
+  // Proposals keep a bit for every member slot
+  const hasVoted = (bitmap: number[], slot: number) =>
+    (bitmap[Math.floor(slot / 8)] & (1 << slot % 8)) != 0;
+
+  type Project = {
+    projectPDA: anchor.web3.PublicKey;
+    projectBump: number;
+    projectId: string;
+  };
+
+  // Signs in the given order until the proposal is approved and returns how many signed
+  const signUntilApproved = async (
+    { projectPDA, projectBump, projectId }: Project,
+    proposal: anchor.web3.PublicKey,
+    kind: object,
+    signers: anchor.web3.Keypair[]
+  ) => {
+    let signed = 0;
+    for (const signer of signers) {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          kind,
+          await memberSlot(projectPDA, signer.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+      signed += 1;
+
+      const current = await projectProgram.account.proposal.fetch(proposal);
+      if (current.state.approved) break;
+    }
+    return signed;
+  };
+
+  const executeProposal = async (
+    { projectPDA, projectBump, projectId }: Project,
+    proposal: anchor.web3.PublicKey,
+    executor: anchor.web3.Keypair = admin,
+    remainingAccounts: anchor.web3.AccountMeta[] = []
+  ) =>
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: executor.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers([executor])
+      .rpc();
+
+  const approveAndExecute = async (
+    project: Project,
+    proposal: anchor.web3.PublicKey,
+    kind: object,
+    signers: anchor.web3.Keypair[],
+    executor: anchor.web3.Keypair = admin,
+    remainingAccounts: anchor.web3.AccountMeta[] = []
+  ) => {
+    await signUntilApproved(project, proposal, kind, signers);
+    return await executeProposal(
+      project,
+      proposal,
+      executor,
+      remainingAccounts
+    );
+  };
+
+  let addProposal: anchor.web3.PublicKey;
+  let deleteProposal: anchor.web3.PublicKey;
+  let timeLimitProposal: anchor.web3.PublicKey;
+  let thresholdProposal: anchor.web3.PublicKey;
+  let transferProposal: anchor.web3.PublicKey;
+
   it("initializes project program", async () => {
     const [projectPDA, projectBump] =
       await anchor.web3.PublicKey.findProgramAddress(
@@ -218,20 +352,9 @@ describe("project", () => {
       projectPDA
     );
 
-    assert.equal(state.add.status, false);
-    assert.equal(state.add.votes, 0);
-    assert.equal(state.add.timestamp, 0);
-
-    assert.equal(state.delete.status, false);
-    assert.equal(state.delete.votes, 0);
-    assert.equal(state.delete.timestamp, 0);
-
-    assert.equal(state.changeThreshold.status, false);
-    assert.equal(state.changeThreshold.votes, 0);
-    assert.equal(state.changeThreshold.timestamp, 0);
-    assert.equal(state.changeThreshold.newThreshold, 0);
-
+    assert.equal(state.proposalCount.toNumber(), 0);
     assert.equal(state.threshold, 1);
+    assert.equal(state.tokenMint.toBase58(), USDCMint.toBase58());
   });
 
   it("Cannot transfer when the threshold or the signatory is only 1", async () => {
@@ -261,41 +384,63 @@ describe("project", () => {
         generalProgram.programId
       );
 
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    [transferProposal] = await getProposalPDA(projectPDA, state.proposalCount.toNumber());
+
     const tx = await projectProgram.methods
       .transferAmountProposal(
         projectBump,
         projectId,
         withdrawAmount1,
-        casTokenAccount
+        casTokenAccount,
+        proposalMetadata("Pay contributor")
       )
       .accounts({
         baseAccount: projectPDA,
+        proposal: transferProposal,
         authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
 
-    const state = await projectProgram.account.projectParameter.fetch(
-      projectPDA
+    const proposal = await projectProgram.account.proposal.fetch(
+      transferProposal
     );
 
-    assert(state.transferAmount.status, true);
+    assert.ok(proposal.state.active);
 
     try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { transferAmount: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: transferProposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+
       const tx = await projectProgram.methods
-        .signTransfer(generalBump, projectBump, projectPoolBump, projectId)
+        .executeProposal(projectBump, projectId)
         .accounts({
           baseAccount: projectPDA,
-          generalAccount: generalPDA,
-          projectPoolAccount: projectPoolPDA,
-          tokenMint: USDCMint,
+          proposal: transferProposal,
           authority: alice.publicKey,
-          walletToWithdrawFrom: casTokenAccount,
-          generalProgram: generalProgram.programId,
           systemProgram: anchor.web3.SystemProgram.programId,
-          tokenProgram: spl.TOKEN_PROGRAM_ID,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
+        .remainingAccounts([
+          { pubkey: projectPoolPDA, isSigner: false, isWritable: true },
+          { pubkey: casTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ])
         .signers([alice])
         .rpc();
     } catch (error) {
@@ -324,7 +469,9 @@ describe("project", () => {
       .accounts({
         baseAccount: projectPDA,
         authority: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
+      .remainingAccounts(memberAccounts(all))
       .signers([alice])
       .rpc();
 
@@ -340,13 +487,38 @@ describe("project", () => {
         .accounts({
           baseAccount: projectPDA,
           authority: bob.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
+        .remainingAccounts(memberAccounts(all))
         .signers([bob])
         .rpc();
     } catch (error) {
       assert.equal(error.error.errorCode.code, "InvalidSigner");
     }
 
+    // The initial signatories are only added once
+    try {
+      await projectProgram.methods
+        .addInitialSignatories(
+          projectBump,
+          projectId,
+          [extra.publicKey, dan.publicKey],
+          threshold,
+          timeLimit
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          authority: alice.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts(memberAccounts([extra.publicKey, dan.publicKey]))
+        .signers([alice])
+        .rpc();
+      throw "added the initial signatories twice";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "SignatoriesAlreadyAdded");
+    }
+
     const state = await projectProgram.account.projectParameter.fetch(
       projectPDA
     );
@@ -380,20 +552,32 @@ describe("project", () => {
       extra.publicKey
     ];
 
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    [addProposal] = await getProposalPDA(projectPDA, state.proposalCount.toNumber());
+
     const tx = await projectProgram.methods
-      .addNewSignatoryProposal(projectBump, projectId, newSigs)
+      .addNewSignatoryProposal(
+        projectBump,
+        projectId,
+        newSigs,
+        [],
+        proposalMetadata("Add signatories")
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: addProposal,
         authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
 
-    const state = await projectProgram.account.projectParameter.fetch(
-      projectPDA
-    );
+    const proposal = await projectProgram.account.proposal.fetch(addProposal);
 
-    assert(state.add.status, true);
+    assert.ok(proposal.state.active);
+    assert.equal(proposal.proposer.toBase58(), admin.publicKey.toBase58());
   });
 
   it("Sign the add proposal", async () => {
@@ -408,22 +592,34 @@ describe("project", () => {
       );
 
     const tx = await projectProgram.methods
-      .signProposal(projectBump, projectId, "add")
+      .signProposal(
+        projectBump,
+        projectId,
+        { addSignatory: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: addProposal,
         authority: alice.publicKey,
       })
       .signers([alice])
       .rpc();
 
-    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.add.votes, 1);
+    let proposal = await projectProgram.account.proposal.fetch(addProposal);
+    assert.equal(proposal.votes, 1);
 
     try {
       await projectProgram.methods
-        .signProposal(projectBump, projectId, "add")
+        .signProposal(
+          projectBump,
+          projectId,
+          { addSignatory: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: addProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -433,19 +629,61 @@ describe("project", () => {
       assert.equal(error.error.errorCode.code, "RepeatedSignature");
     }
 
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { transferAmount: {} },
+          await memberSlot(projectPDA, bob.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: addProposal,
+          authority: bob.publicKey,
+        })
+        .signers([bob])
+        .rpc();
+      throw "signed the wrong kind of proposal";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "WrongProposalKind");
+    }
+
     const tx1 = await projectProgram.methods
-      .signProposal(projectBump, projectId, "add")
+      .signProposal(
+        projectBump,
+        projectId,
+        { addSignatory: {} },
+        await memberSlot(projectPDA, bob.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: addProposal,
         authority: bob.publicKey,
       })
       .signers([bob])
       .rpc();
 
-    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    proposal = await projectProgram.account.proposal.fetch(addProposal);
+    assert.ok(proposal.state.approved);
+
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: addProposal,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(memberAccounts([dan.publicKey, extra.publicKey]))
+      .signers([bob])
+      .rpc();
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
     const lastIndex = state.signatories.length;
 
-    assert.equal(state.add.votes, 0);
+    proposal = await projectProgram.account.proposal.fetch(addProposal);
+    assert.equal(proposal.votes, 2);
     assert.equal(
       state.signatories[lastIndex - 1].key.toBase58(),
       extra.publicKey.toBase58()
@@ -454,13 +692,19 @@ describe("project", () => {
       state.signatories[lastIndex - 2].key.toBase58(),
       dan.publicKey.toBase58()
     );
-    assert.equal(state.add.status, false);
+    assert.ok(proposal.state.executed);
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "add")
+        .signProposal(
+          projectBump,
+          projectId,
+          { addSignatory: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: addProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -486,12 +730,25 @@ describe("project", () => {
         dan.publicKey,
         extra.publicKey
       ];
+
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    [deleteProposal] = await getProposalPDA(projectPDA, state.proposalCount.toNumber());
+
       try {
         const tx = await projectProgram.methods
-      .removeSignatoryProposal(projectBump, projectId, oldSigs)
+      .removeSignatoryProposal(
+        projectBump,
+        projectId,
+        oldSigs,
+        proposalMetadata("Remove signatory")
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: deleteProposal,
         authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
@@ -500,11 +757,11 @@ describe("project", () => {
       }
     
 
-    const state = await projectProgram.account.projectParameter.fetch(
-      projectPDA
+    const proposal = await projectProgram.account.proposal.fetch(
+      deleteProposal
     );
 
-    assert(state.delete.status, true);
+    assert.ok(proposal.state.active);
   });
 
   it("signs the delete proposal", async () => {
@@ -519,22 +776,34 @@ describe("project", () => {
       );
 
     const tx = await projectProgram.methods
-      .signProposal(projectBump, projectId, "delete")
+      .signProposal(
+        projectBump,
+        projectId,
+        { deleteSignatory: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: deleteProposal,
         authority: alice.publicKey,
       })
       .signers([alice])
       .rpc();
 
-    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.delete.votes, 1);
+    let proposal = await projectProgram.account.proposal.fetch(deleteProposal);
+    assert.equal(proposal.votes, 1);
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "delete")
+        .signProposal(
+          projectBump,
+          projectId,
+          { deleteSignatory: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: deleteProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -544,16 +813,37 @@ describe("project", () => {
     }
 
     const tx1 = await projectProgram.methods
-      .signProposal(projectBump, projectId, "delete")
+      .signProposal(
+        projectBump,
+        projectId,
+        { deleteSignatory: {} },
+        await memberSlot(projectPDA, bob.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: deleteProposal,
         authority: bob.publicKey,
       })
       .signers([bob])
       .rpc();
 
-    state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.delete.votes, 0);
+    proposal = await projectProgram.account.proposal.fetch(deleteProposal);
+    assert.ok(proposal.state.approved);
+
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: deleteProposal,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    proposal = await projectProgram.account.proposal.fetch(deleteProposal);
+    assert.ok(proposal.state.executed);
 
     for (let i = 0; i < state.signatories.length; i++) {
       if (state.signatories[i].key.toBase58() == dan.publicKey.toBase58()) {
@@ -566,9 +856,15 @@ describe("project", () => {
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "delete")
+        .signProposal(
+          projectBump,
+          projectId,
+          { deleteSignatory: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: deleteProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -590,21 +886,36 @@ describe("project", () => {
         projectProgram.programId
       );
 
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    [timeLimitProposal] = await getProposalPDA(projectPDA, state.proposalCount.toNumber());
+
     const tx = await projectProgram.methods
-      .changeTimeLimitProposal(projectBump, projectId, newTimeLimit)
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: timeLimitProposal,
         authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
 
-    const state = await projectProgram.account.projectParameter.fetch(
-      projectPDA
+    const proposal = await projectProgram.account.proposal.fetch(
+      timeLimitProposal
     );
 
-    assert.equal(state.changeTimeLimit.status, true);
-    assert.equal(state.changeTimeLimit.newTimeLimit, newTimeLimit);
+    assert.ok(proposal.state.active);
+    assert.equal(
+      proposal.action.changeTimeLimit.newTimeLimit,
+      newTimeLimit
+    );
   });
 
   it("signs the change time out proposal", async () => {
@@ -619,22 +930,34 @@ describe("project", () => {
       );
 
     const tx = await projectProgram.methods
-      .signProposal(projectBump, projectId, "change time limit")
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: timeLimitProposal,
         authority: alice.publicKey,
       })
       .signers([alice])
       .rpc();
 
-    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.changeTimeLimit.votes, 1);
+    let proposal = await projectProgram.account.proposal.fetch(timeLimitProposal);
+    assert.equal(proposal.votes, 1);
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "change time limit")
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: timeLimitProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -644,23 +967,50 @@ describe("project", () => {
     }
 
     const tx1 = await projectProgram.methods
-      .signProposal(projectBump, projectId, "change time limit")
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, bob.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: timeLimitProposal,
         authority: bob.publicKey,
       })
       .signers([bob])
       .rpc();
 
-    state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.changeTimeLimit.votes, 0);
+    proposal = await projectProgram.account.proposal.fetch(timeLimitProposal);
+    assert.ok(proposal.state.approved);
+
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: timeLimitProposal,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    proposal = await projectProgram.account.proposal.fetch(timeLimitProposal);
+    assert.ok(proposal.state.executed);
     assert.equal(state.timeLimit, newTimeLimit);
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "change time limit")
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: timeLimitProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -682,27 +1032,33 @@ describe("project", () => {
         projectProgram.programId
       );
 
-    const currentTimestamp = new Date().getTime() / 1000;
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    [thresholdProposal] = await getProposalPDA(projectPDA, state.proposalCount.toNumber());
 
     const tx = await projectProgram.methods
       .changeThresholdProposal(
         projectBump,
         projectId,
         newThreshold,
-        currentTimestamp
+        proposalMetadata("Change threshold")
       )
       .accounts({
         baseAccount: projectPDA,
+        proposal: thresholdProposal,
         authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
 
-    const state = await projectProgram.account.projectParameter.fetch(
-      projectPDA
+    const proposal = await projectProgram.account.proposal.fetch(
+      thresholdProposal
     );
 
-    assert(state.changeThreshold.status, true);
+    assert.ok(proposal.state.active);
+    assert.equal(proposal.requiredVotes, state.threshold);
   });
 
   it("signs the change threshold proposal", async () => {
@@ -717,22 +1073,34 @@ describe("project", () => {
       );
 
     const tx = await projectProgram.methods
-      .signProposal(projectBump, projectId, "change threshold")
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeThreshold: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: thresholdProposal,
         authority: alice.publicKey,
       })
       .signers([alice])
       .rpc();
 
-    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.changeThreshold.votes, 1);
+    let proposal = await projectProgram.account.proposal.fetch(thresholdProposal);
+    assert.equal(proposal.votes, 1);
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "change threshold")
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeThreshold: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: thresholdProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -742,23 +1110,50 @@ describe("project", () => {
     }
 
     const tx1 = await projectProgram.methods
-      .signProposal(projectBump, projectId, "change threshold")
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeThreshold: {} },
+        await memberSlot(projectPDA, bob.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
+        proposal: thresholdProposal,
         authority: bob.publicKey,
       })
       .signers([bob])
       .rpc();
 
-    state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.changeThreshold.votes, 0);
+    proposal = await projectProgram.account.proposal.fetch(thresholdProposal);
+    assert.ok(proposal.state.approved);
+
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: thresholdProposal,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    proposal = await projectProgram.account.proposal.fetch(thresholdProposal);
+    assert.ok(proposal.state.executed);
     assert.equal(state.threshold, newThreshold);
 
     try {
       const tx = await projectProgram.methods
-        .signProposal(projectBump, projectId, "change threshold")
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeThreshold: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
         .accounts({
           baseAccount: projectPDA,
+          proposal: thresholdProposal,
           authority: alice.publicKey,
         })
         .signers([alice])
@@ -857,44 +1252,120 @@ describe("project", () => {
         projectProgram.programId
       );
 
-    try {
-      const tx = await projectProgram.methods
-        .transferAmountProposal(
-          projectBump,
-          projectId,
-          withdrawAmount1,
-          casTokenAccount
-        )
-        .accounts({
-          baseAccount: projectPDA,
-          authority: admin.publicKey,
-        })
-        .signers([admin])
-        .rpc();
-    } catch (error) {
-      assert.equal(error.error.errorCode.code, "ProposalInProgress");
-    }
-  });
-
-  it("transfer the funds after signing", async () => {
-    const [projectPDA, projectBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from("project"),
-          Buffer.from(projectId.substring(0, 18)),
-          Buffer.from(projectId.substring(18, 36)),
-        ],
-        projectProgram.programId
-      );
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const [firstProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    [transferProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber() + 1
+    );
 
-    const [projectPoolPDA, projectPoolBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from("pool"),
-          Buffer.from(projectId.substring(0, 18)),
-          Buffer.from(projectId.substring(18, 36)),
-        ],
-        projectProgram.programId
+    // Two payouts can be in flight at the same time, each with its own account
+    await projectProgram.methods
+      .transferAmountProposal(
+        projectBump,
+        projectId,
+        withdrawAmount1,
+        adminTokenAccount,
+        proposalMetadata("Pay contributor")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: firstProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await projectProgram.methods
+      .transferAmountProposal(
+        projectBump,
+        projectId,
+        withdrawAmount1,
+        casTokenAccount,
+        proposalMetadata("Pay contributor")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: transferProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const first = await projectProgram.account.proposal.fetch(firstProposal);
+    const second = await projectProgram.account.proposal.fetch(
+      transferProposal
+    );
+
+    assert.equal(first.index.toNumber() + 1, second.index.toNumber());
+    assert.ok(first.state.active);
+    assert.ok(second.state.active);
+    assert.equal(
+      second.action.transferAmount.reciever.toBase58(),
+      casTokenAccount.toBase58()
+    );
+    assert.equal(state.proposalCount.toNumber(), second.index.toNumber() + 1);
+
+    const metadata = proposalMetadata("Pay contributor");
+    assert.equal(second.metadata.title, metadata.title);
+    assert.equal(second.metadata.descriptionUri, metadata.descriptionUri);
+    assert.deepEqual(second.metadata.descriptionHash, metadata.descriptionHash);
+
+    const [rejectedProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .transferAmountProposal(
+          projectBump,
+          projectId,
+          withdrawAmount1,
+          casTokenAccount,
+          proposalMetadata("Pay contributor".repeat(5))
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: rejectedProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "created a proposal with a long title";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TitleTooLong");
+    }
+  });
+
+  it("transfer the funds after signing", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const [projectPoolPDA, projectPoolBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("pool"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
       );
 
     const [generalPDA, generalBump] =
@@ -910,31 +1381,161 @@ describe("project", () => {
 
     // console.log(casTokenAccountBefore.amount);
 
-    const tx = await projectProgram.methods
-      .signTransfer(generalBump, projectBump, projectPoolBump, projectId)
+    try {
+      await projectProgram.methods
+        .executeProposal(projectBump, projectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: transferProposal,
+          authority: dan.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: projectPoolPDA, isSigner: false, isWritable: true },
+          { pubkey: casTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ])
+        .signers([dan])
+        .rpc();
+      throw "executed before approval";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ProposalNotApproved");
+    }
+
+    // Execution is separate from voting, a member who did not sign can carry it out
+    const tx = await approveAndExecute(
+      project,
+      transferProposal,
+      { transferAmount: {} },
+      [alice, admin, bob, cas],
+      dan,
+      [
+        { pubkey: projectPoolPDA, isSigner: false, isWritable: true },
+        { pubkey: casTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ]
+    );
+
+    const casTokenAccountAfter = await spl.getAccount(
+      provider.connection,
+      casTokenAccount
+    );
+
+    const proposal = await projectProgram.account.proposal.fetch(
+      transferProposal
+    );
+    assert.ok(proposal.state.executed);
+    assert.equal(
+      casTokenAccountAfter.amount - casTokenAccountBefore.amount,
+      withdrawAmount1
+    );
+  });
+
+  it("Executes an instruction proposal signed by the project", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const [projectPoolPDA, projectPoolBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("pool"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+
+    const amount = 10;
+    const ix = spl.createTransferInstruction(
+      projectPoolPDA,
+      casTokenAccount,
+      projectPDA,
+      amount
+    );
+
+    const instruction = {
+      programId: ix.programId,
+      accounts: ix.keys.map((key) => ({
+        pubkey: key.pubkey,
+        isSigner: key.isSigner,
+        isWritable: key.isWritable,
+      })),
+      data: ix.data,
+    };
+
+    const state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const [instructionProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    await projectProgram.methods
+      .instructionProposal(
+        projectBump,
+        projectId,
+        instruction,
+        proposalMetadata("Transfer through the token program")
+      )
       .accounts({
         baseAccount: projectPDA,
-        generalAccount: generalPDA,
-        projectPoolAccount: projectPoolPDA,
-        tokenMint: USDCMint,
-        authority: cas.publicKey,
-        walletToWithdrawFrom: casTokenAccount,
-        generalProgram: generalProgram.programId,
+        proposal: instructionProposal,
+        authority: admin.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
-        tokenProgram: spl.TOKEN_PROGRAM_ID,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
-      .signers([cas])
+      .signers([admin])
       .rpc();
 
+    let proposal = await projectProgram.account.proposal.fetch(
+      instructionProposal
+    );
+    assert.equal(
+      proposal.action.instruction.instruction.programId.toBase58(),
+      spl.TOKEN_PROGRAM_ID.toBase58()
+    );
+
+    const casTokenAccountBefore = await spl.getAccount(
+      provider.connection,
+      casTokenAccount
+    );
+
+    await approveAndExecute(
+      project,
+      instructionProposal,
+      { instruction: {} },
+      [alice, admin, bob, cas],
+      admin,
+      [
+        { pubkey: projectPoolPDA, isSigner: false, isWritable: true },
+        { pubkey: casTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ]
+    );
+
+    proposal = await projectProgram.account.proposal.fetch(instructionProposal);
+
     const casTokenAccountAfter = await spl.getAccount(
       provider.connection,
       casTokenAccount
     );
 
+    assert.ok(proposal.state.executed);
+    assert.equal(
+      casTokenAccountAfter.amount - casTokenAccountBefore.amount,
+      amount
+    );
   });
 
-  it("Reduces the number of approvals after 90 days", async () => {
+  it("Executes a batch proposal atomically", async () => {
     const [projectPDA, projectBump] =
       await anchor.web3.PublicKey.findProgramAddress(
         [
@@ -944,108 +1545,3953 @@ describe("project", () => {
         ],
         projectProgram.programId
       );
+    const project = { projectPDA, projectBump, projectId };
+
+    const [projectPoolPDA, projectPoolBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("pool"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
 
-    const timestampAfter90Days = new Date(2022, 9, 17).getTime() / 1000;
-    const currentTimestamp = new Date().getTime() / 1000;
+    const transferAmount = 20;
+    const instructionAmount = 5;
+    const ix = spl.createTransferInstruction(
+      projectPoolPDA,
+      adminTokenAccount,
+      projectPDA,
+      instructionAmount
+    );
 
-    let days = 60 * 60 * 24;
+    const actions = [
+      {
+        transferAmount: { amount: transferAmount, reciever: casTokenAccount },
+      },
+      {
+        instruction: {
+          instruction: {
+            programId: ix.programId,
+            accounts: ix.keys.map((key) => ({
+              pubkey: key.pubkey,
+              isSigner: key.isSigner,
+              isWritable: key.isWritable,
+            })),
+            data: ix.data,
+          },
+        },
+      },
+    ];
 
-    let numberOfDays = (timestampAfter90Days - currentTimestamp) / days;
-    let numberOfMonths = (numberOfDays - 90) / 30 + 1;
+    let state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const [batchProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
 
-    const tx = await projectProgram.methods
-      .changeThresholdProposal(
+    await projectProgram.methods
+      .batchProposal(
         projectBump,
         projectId,
-        fallBackThreshold,
-        timestampAfter90Days
+        actions,
+        proposalMetadata("Quarterly payouts")
       )
       .accounts({
         baseAccount: projectPDA,
+        proposal: batchProposal,
         authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
 
-    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    const newApproval = state.threshold - Math.round(numberOfMonths);
-
-    assert.equal(state.approval, newApproval);
-
-    const timestampAfter100Days = new Date(2022, 9, 27).getTime() / 1000;
+    let proposal = await projectProgram.account.proposal.fetch(batchProposal);
+    assert.equal(proposal.action.batch.actions.length, 2);
+    assert.ok(proposal.state.draft);
 
+    // On its own the transfer fits the pool, together with the batch it does not
     try {
       await projectProgram.methods
-        .changeThresholdProposal(
-          projectBump,
-          projectId,
-          fallBackThreshold,
-          timestampAfter100Days
-        )
+        .addBatchAction(projectBump, projectId, {
+          transferAmount: {
+            amount: state.stakedAmount - transferAmount + 1,
+            reciever: casTokenAccount,
+          },
+        })
         .accounts({
           baseAccount: projectPDA,
+          proposal: batchProposal,
           authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
         .signers([admin])
         .rpc();
+      throw "the batch transfers more than the pool holds";
     } catch (error) {
-      assert.equal(error.error.errorCode.code, "MinimumTimeNotPassed");
+      assert.equal(error.error.errorCode.code, "InsufficientBalance");
     }
 
-    const timestampAfter150Days = new Date(2022, 11, 17).getTime() / 1000;
-    numberOfDays = (timestampAfter150Days - timestampAfter90Days) / days;
-    numberOfMonths = numberOfDays / 30;
-
-    const tx1 = await projectProgram.methods
-      .changeThresholdProposal(
-        projectBump,
-        projectId,
-        fallBackThreshold,
-        timestampAfter150Days
-      )
+    await projectProgram.methods
+      .activateProposal(projectBump, projectId)
       .accounts({
         baseAccount: projectPDA,
+        proposal: batchProposal,
         authority: admin.publicKey,
       })
       .signers([admin])
       .rpc();
 
-    state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.approval, newApproval - Math.round(numberOfMonths));
+    proposal = await projectProgram.account.proposal.fetch(batchProposal);
+    assert.ok(proposal.state.active);
 
-    await projectProgram.methods
-      .signProposal(projectBump, projectId, "change threshold")
-      .accounts({
-        baseAccount: projectPDA,
-        authority: alice.publicKey,
-      })
-      .signers([alice])
-      .rpc();
+    const casTokenAccountBefore = await spl.getAccount(
+      provider.connection,
+      casTokenAccount
+    );
+    const adminTokenAccountBefore = await spl.getAccount(
+      provider.connection,
+      adminTokenAccount
+    );
+
+    await approveAndExecute(
+      project,
+      batchProposal,
+      { batch: {} },
+      [alice, admin, bob, cas],
+      admin,
+      [
+        { pubkey: projectPoolPDA, isSigner: false, isWritable: true },
+        { pubkey: casTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: adminTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ]
+    );
+
+    proposal = await projectProgram.account.proposal.fetch(batchProposal);
+
+    const casTokenAccountAfter = await spl.getAccount(
+      provider.connection,
+      casTokenAccount
+    );
+    const adminTokenAccountAfter = await spl.getAccount(
+      provider.connection,
+      adminTokenAccount
+    );
+
+    assert.ok(proposal.state.executed);
+    assert.equal(
+      casTokenAccountAfter.amount - casTokenAccountBefore.amount,
+      transferAmount
+    );
+    assert.equal(
+      adminTokenAccountAfter.amount - adminTokenAccountBefore.amount,
+      instructionAmount
+    );
+  });
+
+  it("Rejects a proposal once it can no longer pass", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+
+    const state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const [rejectedProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
 
     await projectProgram.methods
-      .signProposal(projectBump, projectId, "change threshold")
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
       .accounts({
         baseAccount: projectPDA,
-        authority: bob.publicKey,
+        proposal: rejectedProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .signers([bob])
+      .signers([admin])
       .rpc();
 
     await projectProgram.methods
-      .signProposal(projectBump, projectId, "change threshold")
+      .rejectProposal(
+        projectBump,
+        projectId,
+        await memberSlot(projectPDA, alice.publicKey)
+      )
       .accounts({
         baseAccount: projectPDA,
-        authority: cas.publicKey,
+        proposal: rejectedProposal,
+        authority: alice.publicKey,
       })
-      .signers([cas])
+      .signers([alice])
       .rpc();
 
-    state = await projectProgram.account.projectParameter.fetch(projectPDA);
-    assert.equal(state.threshold, fallBackThreshold);
+    let proposal = await projectProgram.account.proposal.fetch(
+      rejectedProposal
+    );
+    assert.equal(proposal.rejections, 1);
+
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: rejectedProposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+      throw "approved after rejecting";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "RepeatedSignature");
+    }
+
+    for (const signer of [admin, bob, cas]) {
+      proposal = await projectProgram.account.proposal.fetch(rejectedProposal);
+      if (proposal.state.rejected) break;
+
+      await projectProgram.methods
+        .rejectProposal(
+          projectBump,
+          projectId,
+          await memberSlot(projectPDA, signer.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: rejectedProposal,
+          authority: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    proposal = await projectProgram.account.proposal.fetch(rejectedProposal);
+    assert.ok(proposal.state.rejected);
+    assert.ok(
+      state.signatories.length - proposal.rejections < state.threshold
+    );
+
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, cas.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: rejectedProposal,
+          authority: cas.publicKey,
+        })
+        .signers([cas])
+        .rpc();
+      throw "signed a rejected proposal";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "NoProposalCreated");
+    }
+  });
+
+  it("Revokes a vote and cancels a mistyped transfer", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+
+    const state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const [mistypedProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    await projectProgram.methods
+      .transferAmountProposal(
+        projectBump,
+        projectId,
+        1,
+        adminTokenAccount,
+        proposalMetadata("Pay contributor")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: mistypedProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { transferAmount: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: mistypedProposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+
+    let proposal = await projectProgram.account.proposal.fetch(
+      mistypedProposal
+    );
+    assert.equal(proposal.votes, 1);
+
+    await projectProgram.methods
+      .revokeVote(
+        projectBump,
+        projectId,
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: mistypedProposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+
+    proposal = await projectProgram.account.proposal.fetch(mistypedProposal);
+    assert.equal(proposal.votes, 0);
+    assert.ok(proposal.voters.every((byte) => byte == 0));
+
+    try {
+      await projectProgram.methods
+        .revokeVote(
+          projectBump,
+          projectId,
+          await memberSlot(projectPDA, alice.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: mistypedProposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+      throw "revoked a vote twice";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "NoVoteToRevoke");
+    }
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: mistypedProposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    proposal = await projectProgram.account.proposal.fetch(mistypedProposal);
+    assert.ok(proposal.state.cancelled);
+  });
+
+  it("Keeps the approvals a threshold change was created with", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const createThresholdProposal = async () => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+
+      await projectProgram.methods
+        .changeThresholdProposal(
+          projectBump,
+          projectId,
+          fallBackThreshold,
+          proposalMetadata("Change threshold")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return proposal;
+    };
+
+    thresholdProposal = await createThresholdProposal();
+
+    // The project had a transaction within the last 90 days, so no approval is dropped
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    let proposal = await projectProgram.account.proposal.fetch(
+      thresholdProposal
+    );
+    const requiredVotes = proposal.requiredVotes;
+    assert.equal(requiredVotes, state.approval);
+
+    // The time comes from the cluster clock and a later proposal leaves this one alone
+    await createThresholdProposal();
+    proposal = await projectProgram.account.proposal.fetch(thresholdProposal);
+    assert.equal(proposal.requiredVotes, requiredVotes);
+
+    await approveAndExecute(
+      project,
+      thresholdProposal,
+      { changeThreshold: {} },
+      [alice, bob, cas, admin],
+      cas
+    );
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.threshold, fallBackThreshold);
     assert.equal(state.approval, fallBackThreshold);
-    assert.equal(state.changeThreshold.status, false);
-    assert.equal(state.changeThreshold.votes, 0);
-    assert.equal(state.changeThreshold.timestamp, 0);
+    proposal = await projectProgram.account.proposal.fetch(thresholdProposal);
+    assert.equal(proposal.votes, requiredVotes);
+    assert.ok(proposal.state.executed);
     assert.equal(state.lastReducedThreshold, 0);
   });
+
+  it("Delays execution of approved proposals and lets a member veto them", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const executionDelay = 4;
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [delayProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    await projectProgram.methods
+      .changeExecutionDelayProposal(
+        projectBump,
+        projectId,
+        executionDelay,
+        proposalMetadata("Change execution delay")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: delayProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await approveAndExecute(
+      project,
+      delayProposal,
+      { changeExecutionDelay: {} },
+      [alice, bob]
+    );
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.executionDelay, executionDelay);
+
+    const [vetoedProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: vetoedProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await signUntilApproved(project, vetoedProposal, { changeTimeLimit: {} }, [
+      alice,
+      bob,
+    ]);
+
+    let proposal = await projectProgram.account.proposal.fetch(vetoedProposal);
+    assert.ok(proposal.state.approved);
+
+    try {
+      await projectProgram.methods
+        .executeProposal(projectBump, projectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: vetoedProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "executed during the execution delay";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TimelockNotPassed");
+    }
+
+    // A single member is enough to veto during the delay
+    await projectProgram.methods
+      .objectProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: vetoedProposal,
+        authority: dan.publicKey,
+      })
+      .signers([dan])
+      .rpc();
+
+    proposal = await projectProgram.account.proposal.fetch(vetoedProposal);
+    assert.ok(proposal.state.cancelled);
+
+    const [resetProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber() + 1
+    );
+
+    await projectProgram.methods
+      .changeExecutionDelayProposal(
+        projectBump,
+        projectId,
+        0,
+        proposalMetadata("Change execution delay")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: resetProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await signUntilApproved(
+      project,
+      resetProposal,
+      { changeExecutionDelay: {} },
+      [alice, bob]
+    );
+
+    await new Promise((resolve) =>
+      setTimeout(resolve, (executionDelay + 2) * 1000)
+    );
+
+    try {
+      await projectProgram.methods
+        .objectProposal(projectBump, projectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: resetProposal,
+          authority: dan.publicKey,
+        })
+        .signers([dan])
+        .rpc();
+      throw "objected after the execution delay";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ObjectionWindowPassed");
+    }
+
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: resetProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.executionDelay, 0);
+  });
+
+  it("Shuts down an empty project through a proposal", async () => {
+    const closedProjectId = uuidv4();
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(closedProjectId.substring(0, 18)),
+          Buffer.from(closedProjectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId: closedProjectId };
+    const [projectPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("pool"),
+        Buffer.from(closedProjectId.substring(0, 18)),
+        Buffer.from(closedProjectId.substring(18, 36)),
+      ],
+      projectProgram.programId
+    );
+
+    await projectProgram.methods
+      .initialize(closedProjectId, percentTransfer)
+      .accounts({
+        baseAccount: projectPDA,
+        projectPoolAccount: projectPoolPDA,
+        tokenMint: USDCMint,
+        authority: alice.publicKey,
+        admin: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([alice])
+      .rpc();
+
+    await projectProgram.methods
+      .addInitialSignatories(
+        projectBump,
+        closedProjectId,
+        [admin.publicKey, bob.publicKey],
+        threshold,
+        timeLimit
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        authority: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(memberAccounts([admin.publicKey, bob.publicKey]))
+      .signers([alice])
+      .rpc();
+
+    const [shutdownProposal] = await getProposalPDA(projectPDA, 0);
+
+    await projectProgram.methods
+      .shutdownProposal(
+        projectBump,
+        closedProjectId,
+        proposalMetadata("Shut down")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: shutdownProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await approveAndExecute(project, shutdownProposal, { shutdown: {} }, [
+      alice,
+      bob,
+    ]);
+
+    const state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    assert.ok(state.closed);
+
+    const [nextProposal] = await getProposalPDA(projectPDA, 1);
+    try {
+      await projectProgram.methods
+        .changeTimeLimitProposal(
+          projectBump,
+          closedProjectId,
+          newTimeLimit,
+          proposalMetadata("Change time limit")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: nextProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "created a proposal on a closed project";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ProjectClosed");
+    }
+  });
+
+  it("Expires stale proposals and returns their rent to the proposer", async () => {
+    const shortProjectId = uuidv4();
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(shortProjectId.substring(0, 18)),
+          Buffer.from(shortProjectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const [projectPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("pool"),
+        Buffer.from(shortProjectId.substring(0, 18)),
+        Buffer.from(shortProjectId.substring(18, 36)),
+      ],
+      projectProgram.programId
+    );
+
+    await projectProgram.methods
+      .initialize(shortProjectId, percentTransfer)
+      .accounts({
+        baseAccount: projectPDA,
+        projectPoolAccount: projectPoolPDA,
+        tokenMint: USDCMint,
+        authority: alice.publicKey,
+        admin: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([alice])
+      .rpc();
+
+    // Proposals of this project expire one second after they are created
+    await projectProgram.methods
+      .addInitialSignatories(
+        projectBump,
+        shortProjectId,
+        [admin.publicKey, bob.publicKey],
+        threshold,
+        1
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        authority: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(memberAccounts([admin.publicKey, bob.publicKey]))
+      .signers([alice])
+      .rpc();
+
+    const [staleProposal] = await getProposalPDA(projectPDA, 0);
+
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        shortProjectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: staleProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    let proposal = await projectProgram.account.proposal.fetch(staleProposal);
+    assert.equal(
+      proposal.expiresAt.toNumber(),
+      proposal.timestamp.toNumber() + 1
+    );
+
+    try {
+      await projectProgram.methods
+        .closeProposal(projectBump, shortProjectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: staleProposal,
+          proposer: admin.publicKey,
+        })
+        .rpc();
+      throw "closed an active proposal";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ProposalNotFinished");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await projectProgram.methods
+      .expireProposal(projectBump, shortProjectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: staleProposal,
+      })
+      .rpc();
+
+    proposal = await projectProgram.account.proposal.fetch(staleProposal);
+    assert.ok(proposal.state.expired);
+
+    const rent = await provider.connection.getBalance(staleProposal);
+    const adminBalanceBefore = await provider.connection.getBalance(
+      admin.publicKey
+    );
+
+    await projectProgram.methods
+      .closeProposal(projectBump, shortProjectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: staleProposal,
+        proposer: admin.publicKey,
+      })
+      .rpc();
+
+    const adminBalanceAfter = await provider.connection.getBalance(
+      admin.publicKey
+    );
+    assert.equal(adminBalanceAfter - adminBalanceBefore, rent);
+    assert.equal(
+      await provider.connection.getAccountInfo(staleProposal),
+      null
+    );
+  });
+
+  it("Applies a separate threshold to membership changes", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const membershipThreshold = 4;
+    const membershipThresholds = [
+      { kind: { addSignatory: {} }, threshold: membershipThreshold },
+      { kind: { deleteSignatory: {} }, threshold: membershipThreshold },
+      { kind: { changeThreshold: {} }, threshold: membershipThreshold },
+    ];
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [tableProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    try {
+      await projectProgram.methods
+        .changeKindThresholdsProposal(
+          projectBump,
+          projectId,
+          [membershipThresholds[0], membershipThresholds[0]],
+          proposalMetadata("Change kind thresholds")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: tableProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "created a table with a repeated kind";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "DuplicateProposalKind");
+    }
+
+    await projectProgram.methods
+      .changeKindThresholdsProposal(
+        projectBump,
+        projectId,
+        membershipThresholds,
+        proposalMetadata("Change kind thresholds")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: tableProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await approveAndExecute(
+      project,
+      tableProposal,
+      { changeKindThresholds: {} },
+      [alice, bob]
+    );
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.kindThresholds.length, membershipThresholds.length);
+
+    // Adding a member now needs four signatures while payouts still need two
+    const [addMemberProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    await projectProgram.methods
+      .addNewSignatoryProposal(
+        projectBump,
+        projectId,
+        [extra.publicKey],
+        [],
+        proposalMetadata("Add signatories")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: addMemberProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await signUntilApproved(project, addMemberProposal, { addSignatory: {} }, [
+      alice,
+      bob,
+    ]);
+
+    let proposal = await projectProgram.account.proposal.fetch(
+      addMemberProposal
+    );
+    assert.ok(proposal.state.active);
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: addMemberProposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    // Resetting the table has to meet the strictest threshold in it
+    const [resetProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber() + 1
+    );
+
+    await projectProgram.methods
+      .changeKindThresholdsProposal(
+        projectBump,
+        projectId,
+        membershipThresholds.map((entry) => ({ ...entry, threshold: 0 })),
+        proposalMetadata("Change kind thresholds")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: resetProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    for (const signer of [alice, bob, cas, dan]) {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeKindThresholds: {} },
+          await memberSlot(projectPDA, signer.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: resetProposal,
+          authority: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+
+      proposal = await projectProgram.account.proposal.fetch(resetProposal);
+      assert.equal(proposal.state.approved !== undefined, signer === dan);
+    }
+
+    await projectProgram.methods
+      .executeProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: resetProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.kindThresholds.length, 0);
+  });
+
+  it("Recomputes a percentage threshold when members change", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const createAndExecute = async (
+      create: (proposal: anchor.web3.PublicKey) => Promise<string>,
+      kind: object,
+      signers: anchor.web3.Keypair[],
+      members: anchor.web3.PublicKey[] = []
+    ) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await create(proposal);
+      await approveAndExecute(
+        project,
+        proposal,
+        kind,
+        signers,
+        admin,
+        memberAccounts(members)
+      );
+      return await projectProgram.account.projectParameter.fetch(projectPDA);
+    };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.signatories.length, 5);
+
+    // 60% of 5 members rounds up to 3
+    state = await createAndExecute(
+      (proposal) =>
+        projectProgram.methods
+          .changeThresholdPolicyProposal(
+            projectBump,
+            projectId,
+            { percent: 60, floor: 2, ceiling: 5 },
+            proposalMetadata("Use a percentage threshold")
+          )
+          .accounts({
+            baseAccount: projectPDA,
+            proposal,
+            authority: admin.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc(),
+      { changeThresholdPolicy: {} },
+      [alice, bob]
+    );
+    assert.equal(state.thresholdPolicy.percent, 60);
+    assert.equal(state.threshold, 3);
+
+    // 60% of 6 members rounds up to 4
+    state = await createAndExecute(
+      (proposal) =>
+        projectProgram.methods
+          .addNewSignatoryProposal(
+            projectBump,
+            projectId,
+            [extra.publicKey],
+            [],
+            proposalMetadata("Add signatories")
+          )
+          .accounts({
+            baseAccount: projectPDA,
+            proposal,
+            authority: admin.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc(),
+      { addSignatory: {} },
+      [alice, bob, cas],
+      [extra.publicKey]
+    );
+    assert.equal(state.threshold, 4);
+
+    state = await createAndExecute(
+      (proposal) =>
+        projectProgram.methods
+          .removeSignatoryProposal(
+            projectBump,
+            projectId,
+            [extra.publicKey],
+            proposalMetadata("Remove signatory")
+          )
+          .accounts({
+            baseAccount: projectPDA,
+            proposal,
+            authority: admin.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc(),
+      { deleteSignatory: {} },
+      [alice, bob, cas, dan]
+    );
+    assert.equal(state.threshold, 3);
+
+    // Setting a fixed threshold turns the percentage mode off
+    state = await createAndExecute(
+      (proposal) =>
+        projectProgram.methods
+          .changeThresholdProposal(
+            projectBump,
+            projectId,
+            threshold,
+            proposalMetadata("Change threshold")
+          )
+          .accounts({
+            baseAccount: projectPDA,
+            proposal,
+            authority: admin.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc(),
+      { changeThreshold: {} },
+      [alice, bob, cas]
+    );
+    assert.equal(state.threshold, threshold);
+    assert.equal(state.thresholdPolicy, null);
+  });
+
+  it("Requires more signatures and a delay for large transfers", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+    const [projectPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("pool"),
+        Buffer.from(projectId.substring(0, 18)),
+        Buffer.from(projectId.substring(18, 36)),
+      ],
+      projectProgram.programId
+    );
+
+    const smallAmount = 10;
+    const largeAmount = 100;
+    const tierDelay = 3;
+
+    const signers = [alice, bob, cas, dan];
+    const execute = (proposal: anchor.web3.PublicKey) =>
+      executeProposal(project, proposal, admin, [
+        { pubkey: projectPoolPDA, isSigner: false, isWritable: true },
+        { pubkey: casTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ]);
+
+    const proposeTransfer = async (amount: number) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .transferAmountProposal(
+          projectBump,
+          projectId,
+          amount,
+          casTokenAccount,
+          proposalMetadata("Pay contributor")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return proposal;
+    };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [tierProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    // A tier for another mint would never be enforced
+    try {
+      await projectProgram.methods
+        .changeAmountTiersProposal(
+          projectBump,
+          projectId,
+          [
+            {
+              mint: anchor.web3.Keypair.generate().publicKey,
+              minAmount: new anchor.BN(largeAmount),
+              threshold: 3,
+              delay: tierDelay,
+            },
+          ],
+          proposalMetadata("Add an amount tier")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: tierProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "added a tier for another mint";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidTierMint");
+    }
+
+    await projectProgram.methods
+      .changeAmountTiersProposal(
+        projectBump,
+        projectId,
+        [
+          {
+            mint: USDCMint,
+            minAmount: new anchor.BN(largeAmount),
+            threshold: 3,
+            delay: tierDelay,
+          },
+        ],
+        proposalMetadata("Add an amount tier")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: tierProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    assert.equal(
+      await signUntilApproved(
+        project,
+        tierProposal,
+        { changeAmountTiers: {} },
+        signers
+      ),
+      2
+    );
+    await execute(tierProposal);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.amountTiers.length, 1);
+
+    // Payouts below the tier still need the default threshold and no delay
+    const smallProposal = await proposeTransfer(smallAmount);
+    assert.equal(
+      await signUntilApproved(
+        project,
+        smallProposal,
+        { transferAmount: {} },
+        signers
+      ),
+      2
+    );
+    await execute(smallProposal);
+
+    const largeProposal = await proposeTransfer(largeAmount);
+    assert.equal(
+      await signUntilApproved(
+        project,
+        largeProposal,
+        { transferAmount: {} },
+        signers
+      ),
+      3
+    );
+
+    try {
+      await execute(largeProposal);
+      throw "executed a large transfer during the tier delay";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TimelockNotPassed");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, (tierDelay + 2) * 1000));
+    await execute(largeProposal);
+
+    const proposal = await projectProgram.account.proposal.fetch(
+      largeProposal
+    );
+    assert.ok(proposal.state.executed);
+
+    // The project signs instructions as the pool authority, so even a small transfer through
+    // the token program needs the strictest threshold and waits for the tier delay
+    const ix = spl.createTransferInstruction(
+      projectPoolPDA,
+      casTokenAccount,
+      projectPDA,
+      smallAmount
+    );
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [instructionProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .instructionProposal(
+        projectBump,
+        projectId,
+        {
+          programId: ix.programId,
+          accounts: ix.keys,
+          data: ix.data,
+        },
+        proposalMetadata("Transfer through the token program")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: instructionProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    assert.equal(
+      await signUntilApproved(
+        project,
+        instructionProposal,
+        { instruction: {} },
+        signers
+      ),
+      3
+    );
+
+    try {
+      await execute(instructionProposal);
+      throw "executed an instruction during the tier delay";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TimelockNotPassed");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, (tierDelay + 2) * 1000));
+    await execute(instructionProposal);
+  });
+
+  it("Invalidates proposals created before a threshold change", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const generation = state.configGeneration.toNumber();
+    const [pendingProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    const [thresholdChange] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber() + 1
+    );
+
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: pendingProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await projectProgram.methods
+      .changeThresholdProposal(
+        projectBump,
+        projectId,
+        threshold,
+        proposalMetadata("Change threshold")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: thresholdChange,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    let proposal = await projectProgram.account.proposal.fetch(pendingProposal);
+    assert.equal(proposal.configGeneration.toNumber(), generation);
+
+    await approveAndExecute(project, thresholdChange, { changeThreshold: {} }, [
+      alice,
+      bob,
+    ]);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.configGeneration.toNumber(), generation + 1);
+
+    // The pending proposal was created under the old threshold
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: pendingProposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+      throw "signed a stale proposal";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "StaleProposal");
+    }
+
+    await projectProgram.methods
+      .expireProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: pendingProposal,
+      })
+      .rpc();
+
+    proposal = await projectProgram.account.proposal.fetch(pendingProposal);
+    assert.ok(proposal.state.expired);
+  });
+
+  it("Lets any member create proposals", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+
+    const outsider = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        outsider.publicKey,
+        10000000000
+      ),
+      "confirmed"
+    );
+
+    const state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const [memberProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+
+    try {
+      await projectProgram.methods
+        .changeTimeLimitProposal(
+          projectBump,
+          projectId,
+          newTimeLimit,
+          proposalMetadata("Change time limit")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: memberProposal,
+          authority: outsider.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([outsider])
+        .rpc();
+      throw "an outsider created a proposal";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidSigner");
+    }
+
+    // bob is a member but not the project authority
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: memberProposal,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+
+    const proposal = await projectProgram.account.proposal.fetch(
+      memberProposal
+    );
+    assert.equal(proposal.proposer.toBase58(), bob.publicKey.toBase58());
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: memberProposal,
+        authority: bob.publicKey,
+      })
+      .signers([bob])
+      .rpc();
+  });
+
+  it("Restricts members to the permissions given to them", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const changePermissions = async (
+      signatory: anchor.web3.PublicKey,
+      permissions: number,
+      executor: anchor.web3.Keypair,
+      proposer: anchor.web3.Keypair = admin
+    ) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .changePermissionsProposal(
+          projectBump,
+          projectId,
+          signatory,
+          permissions,
+          proposalMetadata("Change permissions")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: proposer.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([proposer])
+        .rpc();
+      await approveAndExecute(
+        project,
+        proposal,
+        { changePermissions: {} },
+        [alice, bob],
+        executor
+      );
+      return proposal;
+    };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const danIndex = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == dan.publicKey.toBase58()
+    );
+    assert.equal(state.signatories[danIndex].permissions, ALL);
+
+    // dan becomes a bot that can only execute approved proposals
+    await changePermissions(dan.publicKey, EXECUTE, admin);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.signatories[danIndex].permissions, EXECUTE);
+
+    // admin is the project authority, but as a member it needs the permission as well
+    await changePermissions(admin.publicKey, ALL & ~PROPOSE, admin);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [authorityDraft] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .changeTimeLimitProposal(
+          projectBump,
+          projectId,
+          newTimeLimit,
+          proposalMetadata("Change time limit")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: authorityDraft,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "the authority proposed without the propose permission";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidSigner");
+    }
+
+    await changePermissions(admin.publicKey, ALL, admin, alice);
+
+    const [draft] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .changeTimeLimitProposal(
+          projectBump,
+          projectId,
+          newTimeLimit,
+          proposalMetadata("Change time limit")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: draft,
+          authority: dan.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([dan])
+        .rpc();
+      throw "proposed without the propose permission";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidSigner");
+    }
+
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: draft,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, dan.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: draft,
+          authority: dan.publicKey,
+        })
+        .signers([dan])
+        .rpc();
+      throw "voted without the vote permission";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "MissingPermission");
+    }
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: draft,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    const changeThreshold = async (threshold: number) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .changeThresholdProposal(
+          projectBump,
+          projectId,
+          threshold,
+          proposalMetadata("Change threshold")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await approveAndExecute(project, proposal, { changeThreshold: {} }, [
+        alice,
+        admin,
+        bob,
+        cas,
+      ]);
+    };
+
+    // Every member left with a vote is needed, so none of them can give it up
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const threshold = state.threshold;
+    const votingWeight = state.signatories
+      .filter((signatory) => (signatory.permissions & VOTE) != 0)
+      .reduce((total, signatory) => total + signatory.weight, 0);
+    await changeThreshold(votingWeight);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [stripProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .changePermissionsProposal(
+          projectBump,
+          projectId,
+          alice.publicKey,
+          PROPOSE | EXECUTE,
+          proposalMetadata("Change permissions")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: stripProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "left less voting weight than the threshold";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ThresholdIsMore");
+    }
+
+    await changeThreshold(threshold);
+
+    // The bot executes the proposal that gives it back every permission
+    await changePermissions(dan.publicKey, ALL, dan);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.signatories[danIndex].permissions, ALL);
+  });
+
+  it("Counts votes by the weight of each signatory", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const changeWeight = async (
+      signatory: anchor.web3.PublicKey,
+      weight: number
+    ) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .changeWeightProposal(
+          projectBump,
+          projectId,
+          signatory,
+          weight,
+          proposalMetadata("Change weight")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await approveAndExecute(project, proposal, { changeWeight: {} }, [
+        alice,
+        bob,
+      ]);
+    };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [invalid] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    for (const weight of [0, 1_000_001]) {
+      try {
+        await projectProgram.methods
+          .changeWeightProposal(
+            projectBump,
+            projectId,
+            alice.publicKey,
+            weight,
+            proposalMetadata("Change weight")
+          )
+          .accounts({
+            baseAccount: projectPDA,
+            proposal: invalid,
+            authority: admin.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        throw `created a proposal for a weight of ${weight}`;
+      } catch (error) {
+        assert.equal(error.error.errorCode.code, "InvalidWeight");
+      }
+    }
+
+    // alice alone now carries the whole threshold
+    await changeWeight(alice.publicKey, 2);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const aliceIndex = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == alice.publicKey.toBase58()
+    );
+    assert.equal(state.signatories[aliceIndex].weight, 2);
+
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+
+    const approved = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(approved.votes, 1);
+    assert.equal(approved.approvalWeight.toNumber(), 2);
+    assert.ok(approved.state.approved);
+
+    // Cancellations are counted in weight as well
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+
+    const cancelled = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(cancelled.cancellations, 1);
+    assert.equal(cancelled.cancellationWeight.toNumber(), 2);
+    assert.ok(cancelled.state.cancelled);
+
+    const changeThreshold = async (threshold: number) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .changeThresholdProposal(
+          projectBump,
+          projectId,
+          threshold,
+          proposalMetadata("Change threshold")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await approveAndExecute(project, proposal, { changeThreshold: {} }, [
+        alice,
+        admin,
+        bob,
+        cas,
+        dan,
+      ]);
+    };
+
+    // Lowering a weight cannot leave the threshold out of reach of the members
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const threshold = state.threshold;
+    const votingWeight = state.signatories
+      .filter((signatory) => (signatory.permissions & VOTE) != 0)
+      .reduce((total, signatory) => total + signatory.weight, 0);
+    await changeThreshold(votingWeight);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [lowerProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .changeWeightProposal(
+          projectBump,
+          projectId,
+          alice.publicKey,
+          1,
+          proposalMetadata("Change weight")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: lowerProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "left less voting weight than the threshold";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "ThresholdIsMore");
+    }
+
+    await changeThreshold(threshold);
+    await changeWeight(alice.publicKey, 1);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.signatories[aliceIndex].weight, 1);
+  });
+
+  it("Lets a member delegate their vote", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+
+    try {
+      await projectProgram.methods
+        .delegateVote(
+          projectBump,
+          projectId,
+          bob.publicKey,
+          null,
+          new anchor.BN(0)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          authority: bob.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([bob])
+        .rpc();
+      throw "delegated a vote to the delegator";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidDelegation");
+    }
+
+    // bob goes on leave and hands their vote on time limit changes to an outside key
+    await projectProgram.methods
+      .delegateVote(
+        projectBump,
+        projectId,
+        extra.publicKey,
+        { changeTimeLimit: {} },
+        new anchor.BN(0)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.delegations.length, 1);
+    assert.equal(
+      state.delegations[0].delegate.toBase58(),
+      extra.publicKey.toBase58()
+    );
+
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    try {
+      await projectProgram.methods
+        .signProposalAsDelegate(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          alice.publicKey,
+          await memberSlot(projectPDA, alice.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: extra.publicKey,
+        })
+        .signers([extra])
+        .rpc();
+      throw "signed for a member without a delegation";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "NotDelegate");
+    }
+
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+    await projectProgram.methods
+      .signProposalAsDelegate(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        bob.publicKey,
+        await memberSlot(projectPDA, bob.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: extra.publicKey,
+      })
+      .signers([extra])
+      .rpc();
+
+    const approved = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(approved.votes, 2);
+    const bobSlot = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == bob.publicKey.toBase58()
+    );
+    assert.ok(hasVoted(approved.voters, bobSlot));
+    assert.ok(approved.state.approved);
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    // A member only gets a few delegations, so one member cannot use up the project's
+    const delegateTo = (kind) =>
+      projectProgram.methods
+        .delegateVote(
+          projectBump,
+          projectId,
+          extra.publicKey,
+          kind,
+          new anchor.BN(0)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          authority: bob.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([bob])
+        .rpc();
+    const kinds = [{ changeThreshold: {} }, { transferAmount: {} }];
+    for (const kind of kinds) {
+      await delegateTo(kind);
+    }
+    try {
+      await delegateTo({ shutdown: {} });
+      throw "delegated more than a member's share";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TooManyDelegations");
+    }
+    for (const kind of kinds) {
+      await projectProgram.methods
+        .revokeDelegation(projectBump, projectId, kind)
+        .accounts({
+          baseAccount: projectPDA,
+          authority: bob.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([bob])
+        .rpc();
+    }
+
+    await projectProgram.methods
+      .revokeDelegation(projectBump, projectId, { changeTimeLimit: {} })
+      .accounts({
+        baseAccount: projectPDA,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.delegations.length, 0);
+
+    try {
+      await projectProgram.methods
+        .revokeDelegation(projectBump, projectId, { changeTimeLimit: {} })
+        .accounts({
+          baseAccount: projectPDA,
+          authority: bob.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([bob])
+        .rpc();
+      throw "revoked a delegation twice";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "DelegationNotFound");
+    }
+  });
+
+  it("Lets a signatory rotate their own key", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const [rotation] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("rotation"),
+        projectPDA.toBuffer(),
+        dan.publicKey.toBuffer(),
+      ],
+      projectProgram.programId
+    );
+
+    const newDan = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(newDan.publicKey, 10000000000),
+      "confirmed"
+    );
+
+    const requestRotation = async () => {
+      await projectProgram.methods
+        .requestKeyRotation(projectBump, projectId)
+        .accounts({
+          baseAccount: projectPDA,
+          rotation,
+          authority: dan.publicKey,
+          newAuthority: newDan.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([dan, newDan])
+        .rpc();
+    };
+
+    // Another member stops a rotation they do not trust
+    await requestRotation();
+    await projectProgram.methods
+      .objectKeyRotation(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        rotation,
+        newKey: newDan.publicKey,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+    assert.equal(await provider.connection.getAccountInfo(rotation), null);
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const danIndex = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == dan.publicKey.toBase58()
+    );
+    const before = state.signatories[danIndex];
+
+    // dan votes on a proposal that is still open when the key is rotated
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, dan.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: dan.publicKey,
+      })
+      .signers([dan])
+      .rpc();
+
+    await requestRotation();
+    await projectProgram.methods
+      .completeKeyRotation(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        rotation,
+        newAuthority: newDan.publicKey,
+      })
+      .signers([newDan])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const after = state.signatories[danIndex];
+    assert.equal(after.key.toBase58(), newDan.publicKey.toBase58());
+    assert.equal(after.previousKey.toBase58(), dan.publicKey.toBase58());
+    assert.equal(after.permissions, before.permissions);
+    assert.equal(after.weight, before.weight);
+
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          projectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, newDan.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: newDan.publicKey,
+        })
+        .signers([newDan])
+        .rpc();
+      throw "voted twice through the rotated key";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "RepeatedSignature");
+    }
+
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+    const approved = await projectProgram.account.proposal.fetch(proposal);
+    assert.ok(approved.state.approved);
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    dan = newDan;
+  });
+
+  it("Lets a child project vote as a single signatory", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const childProjectId = uuidv4();
+    const [childPDA, childBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(childProjectId.substring(0, 18)),
+          Buffer.from(childProjectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const [childPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("pool"),
+        Buffer.from(childProjectId.substring(0, 18)),
+        Buffer.from(childProjectId.substring(18, 36)),
+      ],
+      projectProgram.programId
+    );
+
+    // A department multisig where any single member can act
+    await projectProgram.methods
+      .initialize(childProjectId, percentTransfer)
+      .accounts({
+        baseAccount: childPDA,
+        projectPoolAccount: childPoolPDA,
+        tokenMint: USDCMint,
+        authority: alice.publicKey,
+        admin: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([alice])
+      .rpc();
+    await projectProgram.methods
+      .addInitialSignatories(
+        childBump,
+        childProjectId,
+        [bob.publicKey, cas.publicKey],
+        1,
+        timeLimit
+      )
+      .accounts({
+        baseAccount: childPDA,
+        authority: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(memberAccounts([bob.publicKey, cas.publicKey]))
+      .signers([alice])
+      .rpc();
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [selfProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .addNewSignatoryProposal(
+          projectBump,
+          projectId,
+          [projectPDA],
+          [],
+          proposalMetadata("Add signatories")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: selfProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "added the project as its own signatory";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidChildProject");
+    }
+
+    // Another project cannot slip in as a plain key past the checks for child projects
+    const [plainProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .addNewSignatoryProposal(
+        projectBump,
+        projectId,
+        [childPDA],
+        [],
+        proposalMetadata("Add signatories")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: plainProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await signUntilApproved(project, plainProposal, { addSignatory: {} }, [
+      alice,
+      bob,
+    ]);
+    try {
+      await projectProgram.methods
+        .executeProposal(projectBump, projectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: plainProposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts(memberAccounts([childPDA]))
+        .signers([admin])
+        .rpc();
+      throw "added a project as a plain signatory";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidChildProject");
+    }
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: plainProposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [addProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .addProjectSignatoryProposal(
+        projectBump,
+        projectId,
+        proposalMetadata("Add the department")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: addProposal,
+        childProject: childPDA,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await approveAndExecute(
+      project,
+      addProposal,
+      { addProjectSignatory: {} },
+      [alice, bob],
+      admin,
+      memberAccounts([childPDA])
+    );
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const child = state.signatories.find(
+      (signatory) => signatory.key.toBase58() == childPDA.toBase58()
+    );
+    assert.ok(child.isProject);
+
+    // The parent now has a project among its members, so it cannot become a child of any
+    // project, which keeps the projects from ever forming a cycle
+    const [cycleProposal] = await getProposalPDA(childPDA, 0);
+    try {
+      await projectProgram.methods
+        .addProjectSignatoryProposal(
+          childBump,
+          childProjectId,
+          proposalMetadata("Add the parent")
+        )
+        .accounts({
+          baseAccount: childPDA,
+          proposal: cycleProposal,
+          childProject: projectPDA,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "added the parent project as a child of its child";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidChildProject");
+    }
+
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    // The child votes by executing its own proposal that signs the parent proposal
+    const ix = await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, childPDA)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: childPDA,
+      })
+      .instruction();
+    const instruction = {
+      programId: ix.programId,
+      accounts: ix.keys.map((key) => ({
+        pubkey: key.pubkey,
+        isSigner: key.isSigner,
+        isWritable: key.isWritable,
+      })),
+      data: ix.data,
+    };
+
+    const [childProposal] = await getProposalPDA(childPDA, 0);
+    await projectProgram.methods
+      .instructionProposal(
+        childBump,
+        childProjectId,
+        instruction,
+        proposalMetadata("Vote on the time limit")
+      )
+      .accounts({
+        baseAccount: childPDA,
+        proposal: childProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await projectProgram.methods
+      .signProposal(
+        childBump,
+        childProjectId,
+        { instruction: {} },
+        await memberSlot(childPDA, bob.publicKey)
+      )
+      .accounts({
+        baseAccount: childPDA,
+        proposal: childProposal,
+        authority: bob.publicKey,
+      })
+      .signers([bob])
+      .rpc();
+    await projectProgram.methods
+      .executeProposal(childBump, childProjectId)
+      .accounts({
+        baseAccount: childPDA,
+        proposal: childProposal,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: projectPDA, isSigner: false, isWritable: true },
+        { pubkey: proposal, isSigner: false, isWritable: true },
+        {
+          pubkey: projectProgram.programId,
+          isSigner: false,
+          isWritable: false,
+        },
+      ])
+      .signers([bob])
+      .rpc();
+
+    const signed = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(signed.votes, 1);
+    const childSlot = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == childPDA.toBase58()
+    );
+    assert.ok(hasVoted(signed.voters, childSlot));
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+  });
+
+  it("Keeps a project that joined as a plain member from voting", async () => {
+    const projectPDAs = [];
+    for (const id of [uuidv4(), uuidv4()]) {
+      const [projectPDA, projectBump] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [
+            Buffer.from("project"),
+            Buffer.from(id.substring(0, 18)),
+            Buffer.from(id.substring(18, 36)),
+          ],
+          projectProgram.programId
+        );
+      const [projectPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("pool"),
+          Buffer.from(id.substring(0, 18)),
+          Buffer.from(id.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+      projectPDAs.push({ id, projectPDA, projectBump, projectPoolPDA });
+    }
+    const [parent, late] = projectPDAs;
+
+    // The parent adds the address of a project that does not exist yet as a plain member
+    const members = [
+      [admin.publicKey, late.projectPDA],
+      [admin.publicKey, bob.publicKey],
+    ];
+    for (const [i, project] of [parent, late].entries()) {
+      await projectProgram.methods
+        .initialize(project.id, percentTransfer)
+        .accounts({
+          baseAccount: project.projectPDA,
+          projectPoolAccount: project.projectPoolPDA,
+          tokenMint: USDCMint,
+          authority: alice.publicKey,
+          admin: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([alice])
+        .rpc();
+      await projectProgram.methods
+        .addInitialSignatories(
+          project.projectBump,
+          project.id,
+          members[i],
+          threshold,
+          timeLimit
+        )
+        .accounts({
+          baseAccount: project.projectPDA,
+          authority: alice.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts(memberAccounts(members[i]))
+        .signers([alice])
+        .rpc();
+    }
+
+    const [proposal] = await getProposalPDA(parent.projectPDA, 0);
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        parent.projectBump,
+        parent.id,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: parent.projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    const ix = await projectProgram.methods
+      .signProposal(
+        parent.projectBump,
+        parent.id,
+        { changeTimeLimit: {} },
+        await memberSlot(parent.projectPDA, late.projectPDA)
+      )
+      .accounts({
+        baseAccount: parent.projectPDA,
+        proposal,
+        authority: late.projectPDA,
+      })
+      .instruction();
+    const [lateProposal] = await getProposalPDA(late.projectPDA, 0);
+    await projectProgram.methods
+      .instructionProposal(
+        late.projectBump,
+        late.id,
+        {
+          programId: ix.programId,
+          accounts: ix.keys,
+          data: ix.data,
+        },
+        proposalMetadata("Vote on the time limit")
+      )
+      .accounts({
+        baseAccount: late.projectPDA,
+        proposal: lateProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    const lateProject = {
+      projectPDA: late.projectPDA,
+      projectBump: late.projectBump,
+      projectId: late.id,
+    };
+    await signUntilApproved(lateProject, lateProposal, { instruction: {} }, [
+      alice,
+      bob,
+    ]);
+
+    try {
+      await executeProposal(lateProject, lateProposal, admin, [
+        { pubkey: parent.projectPDA, isSigner: false, isWritable: true },
+        { pubkey: proposal, isSigner: false, isWritable: true },
+        {
+          pubkey: projectProgram.programId,
+          isSigner: false,
+          isWritable: false,
+        },
+      ]);
+      throw "a project voted as a plain member";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidChildProject");
+    }
+  });
+
+  it("Lets guardians recover a lost member key after a delay", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+    const [recovery] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("recovery"),
+        projectPDA.toBuffer(),
+        cas.publicKey.toBuffer(),
+      ],
+      projectProgram.programId
+    );
+
+    const guardians = [
+      anchor.web3.Keypair.generate(),
+      anchor.web3.Keypair.generate(),
+    ];
+    for (const guardian of guardians) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          guardian.publicKey,
+          10000000000
+        ),
+        "confirmed"
+      );
+    }
+    const recoveryDelay = 60 * 60 * 24 * 7;
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .changeGuardiansProposal(
+          projectBump,
+          projectId,
+          guardians.map((guardian) => guardian.publicKey),
+          2,
+          60,
+          proposalMetadata("Set guardians")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "set guardians with a short recovery delay";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidGuardians");
+    }
+
+    await projectProgram.methods
+      .changeGuardiansProposal(
+        projectBump,
+        projectId,
+        guardians.map((guardian) => guardian.publicKey),
+        2,
+        recoveryDelay,
+        proposalMetadata("Set guardians")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await approveAndExecute(project, proposal, { changeGuardians: {} }, [
+      alice,
+      bob,
+    ]);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.guardians.length, 2);
+    assert.equal(state.guardianThreshold, 2);
+    assert.equal(state.recoveryDelay, recoveryDelay);
+
+    const newCas = anchor.web3.Keypair.generate();
+    await projectProgram.methods
+      .startRecovery(projectBump, projectId, cas.publicKey, newCas.publicKey)
+      .accounts({
+        baseAccount: projectPDA,
+        recovery,
+        guardian: guardians[0].publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([guardians[0]])
+      .rpc();
+
+    const completeRecovery = async () => {
+      await projectProgram.methods
+        .completeRecovery(projectBump, projectId)
+        .accounts({
+          baseAccount: projectPDA,
+          recovery,
+          initiator: guardians[0].publicKey,
+          authority: newCas.publicKey,
+        })
+        .signers([newCas])
+        .rpc();
+    };
+
+    try {
+      await completeRecovery();
+      throw "recovered with a single guardian";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "RecoveryNotApproved");
+    }
+
+    await projectProgram.methods
+      .approveRecovery(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        recovery,
+        guardian: guardians[1].publicKey,
+      })
+      .signers([guardians[1]])
+      .rpc();
+
+    const pending = await projectProgram.account.recovery.fetch(recovery);
+    assert.equal(pending.approvals.length, 2);
+    assert.ok(pending.executableAt.toNumber() > 0);
+
+    try {
+      await completeRecovery();
+      throw "recovered before the delay";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TimelockNotPassed");
+    }
+
+    // cas still holds the key and stops the recovery of their seat
+    await projectProgram.methods
+      .cancelRecovery(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        recovery,
+        initiator: guardians[0].publicKey,
+        authority: cas.publicKey,
+      })
+      .signers([cas])
+      .rpc();
+    assert.equal(await provider.connection.getAccountInfo(recovery), null);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.ok(
+      state.signatories.some(
+        (signatory) => signatory.key.toBase58() == cas.publicKey.toBase58()
+      )
+    );
+  });
+
+  it("Lets governance token holders vote instead of signatories", async () => {
+    const daoProjectId = uuidv4();
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(daoProjectId.substring(0, 18)),
+          Buffer.from(daoProjectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId: daoProjectId };
+    const [projectPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("pool"),
+        Buffer.from(daoProjectId.substring(0, 18)),
+        Buffer.from(daoProjectId.substring(18, 36)),
+      ],
+      projectProgram.programId
+    );
+
+    await projectProgram.methods
+      .initialize(daoProjectId, percentTransfer)
+      .accounts({
+        baseAccount: projectPDA,
+        projectPoolAccount: projectPoolPDA,
+        tokenMint: USDCMint,
+        authority: alice.publicKey,
+        admin: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([alice])
+      .rpc();
+    // A new project only takes the space of its creator as the single signatory
+    const initialSize = (await provider.connection.getAccountInfo(projectPDA))
+      .data.length;
+    assert.equal(initialSize, 231);
+    await projectProgram.methods
+      .addInitialSignatories(
+        projectBump,
+        daoProjectId,
+        [admin.publicKey, bob.publicKey],
+        threshold,
+        timeLimit
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        authority: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(memberAccounts([admin.publicKey, bob.publicKey]))
+      .signers([alice])
+      .rpc();
+
+    const governanceMint = await spl.createMint(
+      provider.connection,
+      admin,
+      admin.publicKey,
+      null,
+      0
+    );
+    const tokenThreshold = 600;
+
+    const [governanceProposal] = await getProposalPDA(projectPDA, 0);
+    await projectProgram.methods
+      .changeGovernanceProposal(
+        projectBump,
+        daoProjectId,
+        governanceMint,
+        new anchor.BN(tokenThreshold),
+        proposalMetadata("Hand the project to token holders")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: governanceProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await approveAndExecute(
+      project,
+      governanceProposal,
+      { changeGovernance: {} },
+      [alice, bob]
+    );
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.governanceMint.toBase58(), governanceMint.toBase58());
+
+    // Locks governance tokens of the voter in their own escrow
+    const escrows = {};
+    const lockTokens = async (voter, amount, mint = governanceMint) => {
+      const [escrow] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("escrow"),
+          projectPDA.toBuffer(),
+          mint.toBuffer(),
+          voter.publicKey.toBuffer(),
+        ],
+        projectProgram.programId
+      );
+      const [vault] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("escrow_vault"),
+          projectPDA.toBuffer(),
+          mint.toBuffer(),
+          voter.publicKey.toBuffer(),
+        ],
+        projectProgram.programId
+      );
+      const tokenAccount = await spl.createAccount(
+        provider.connection,
+        voter,
+        mint,
+        voter.publicKey
+      );
+      await spl.mintTo(
+        provider.connection,
+        voter,
+        mint,
+        tokenAccount,
+        admin.publicKey,
+        amount,
+        [admin]
+      );
+
+      await projectProgram.methods
+        .createVoterEscrow(projectBump, daoProjectId)
+        .accounts({
+          baseAccount: projectPDA,
+          escrow,
+          vault,
+          governanceMint: mint,
+          voter: voter.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([voter])
+        .rpc();
+      await projectProgram.methods
+        .depositGovernanceTokens(projectBump, daoProjectId, new anchor.BN(amount))
+        .accounts({
+          baseAccount: projectPDA,
+          escrow,
+          vault,
+          voterTokenAccount: tokenAccount,
+          voter: voter.publicKey,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([voter])
+        .rpc();
+      escrows[voter.publicKey.toBase58()] = { escrow, vault, tokenAccount };
+    };
+
+    // cas holds a single token and, without being a member, proposes
+    await lockTokens(cas, 1);
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        daoProjectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: cas.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([cas])
+      .rpc();
+
+    // A single token voting no while few tokens are deposited does not reject the proposal
+    const [casRecord] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote"), proposal.toBuffer(), cas.publicKey.toBuffer()],
+      projectProgram.programId
+    );
+    await projectProgram.methods
+      .castTokenVote(projectBump, daoProjectId, { changeTimeLimit: {} }, false)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        escrow: escrows[cas.publicKey.toBase58()].escrow,
+        voteRecord: casRecord,
+        voter: cas.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([cas])
+      .rpc();
+    const opposed = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(opposed.rejectionWeight.toNumber(), 1);
+    assert.ok(opposed.state.active);
+
+    // alice and bob each lock 500 governance tokens in their escrow
+    for (const voter of [alice, bob]) {
+      await lockTokens(voter, 500);
+    }
+
+    // The old signatories can no longer cancel what the token holders vote on
+    try {
+      await projectProgram.methods
+        .cancelProposal(projectBump, daoProjectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+      throw "a signatory cancelled in token mode";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TokenVotingEnabled");
+    }
+
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          daoProjectId,
+          { changeTimeLimit: {} },
+          await memberSlot(projectPDA, alice.publicKey)
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+      throw "a signatory voted in token mode";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TokenVotingEnabled");
+    }
+
+    for (const voter of [alice, bob]) {
+      const [voteRecord] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("vote"), proposal.toBuffer(), voter.publicKey.toBuffer()],
+        projectProgram.programId
+      );
+      await projectProgram.methods
+        .castTokenVote(projectBump, daoProjectId, { changeTimeLimit: {} }, true)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          escrow: escrows[voter.publicKey.toBase58()].escrow,
+          voteRecord,
+          voter: voter.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    }
+
+    const approved = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(approved.approvalWeight.toNumber(), 1000);
+    assert.ok(approved.state.approved);
+
+    try {
+      await projectProgram.methods
+        .objectProposal(projectBump, daoProjectId)
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: alice.publicKey,
+        })
+        .signers([alice])
+        .rpc();
+      throw "a signatory vetoed in token mode";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TokenVotingEnabled");
+    }
+
+    const aliceEscrow = escrows[alice.publicKey.toBase58()];
+    try {
+      await projectProgram.methods
+        .withdrawGovernanceTokens(projectBump, daoProjectId, new anchor.BN(500))
+        .accounts({
+          baseAccount: projectPDA,
+          escrow: aliceEscrow.escrow,
+          vault: aliceEscrow.vault,
+          voterTokenAccount: aliceEscrow.tokenAccount,
+          voter: alice.publicKey,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([alice])
+        .rpc();
+      throw "withdrew tokens that are still voting";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "EscrowLocked");
+    }
+
+    // Anyone can execute once the token holders approved
+    await projectProgram.methods
+      .executeProposal(projectBump, daoProjectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: cas.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([cas])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.timeLimit, newTimeLimit);
+
+    // The holders move to a new mint and lock it in new escrows
+    const newMint = await spl.createMint(
+      provider.connection,
+      admin,
+      admin.publicKey,
+      null,
+      0
+    );
+    const [mintProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeGovernanceProposal(
+        projectBump,
+        daoProjectId,
+        newMint,
+        new anchor.BN(tokenThreshold),
+        proposalMetadata("Move to a new governance mint")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: mintProposal,
+        authority: cas.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([cas])
+      .rpc();
+    for (const voter of [alice, bob]) {
+      const [voteRecord] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("vote"),
+          mintProposal.toBuffer(),
+          voter.publicKey.toBuffer(),
+        ],
+        projectProgram.programId
+      );
+      await projectProgram.methods
+        .castTokenVote(
+          projectBump,
+          daoProjectId,
+          { changeGovernance: {} },
+          true
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: mintProposal,
+          escrow: escrows[voter.publicKey.toBase58()].escrow,
+          voteRecord,
+          voter: voter.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    }
+    await projectProgram.methods
+      .executeProposal(projectBump, daoProjectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: mintProposal,
+        authority: cas.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([cas])
+      .rpc();
+
+    await lockTokens(alice, 700, newMint);
+    const newEscrow = await projectProgram.account.voterEscrow.fetch(
+      escrows[alice.publicKey.toBase58()].escrow
+    );
+    assert.equal(newEscrow.mint.toBase58(), newMint.toBase58());
+    assert.equal(newEscrow.amount.toNumber(), 700);
+  });
+
+  it("Resizes the project with its data and caps its members", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    const changeMaxMembers = async (maxMembers: number) => {
+      const state = await projectProgram.account.projectParameter.fetch(
+        projectPDA
+      );
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .changeMaxMembersProposal(
+          projectBump,
+          projectId,
+          maxMembers,
+          proposalMetadata("Change max members")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await approveAndExecute(project, proposal, { changeMaxMembers: {} }, [
+        alice,
+        bob,
+      ]);
+    };
+
+    // A delegation grows the account and revoking it gives the space back
+    const sizeBefore = (await provider.connection.getAccountInfo(projectPDA))
+      .data.length;
+    await projectProgram.methods
+      .delegateVote(
+        projectBump,
+        projectId,
+        extra.publicKey,
+        null,
+        new anchor.BN(0)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+    const sizeDelegated = (await provider.connection.getAccountInfo(projectPDA))
+      .data.length;
+    assert.equal(sizeDelegated - sizeBefore, 32 + 32 + 1 + 8);
+
+    await projectProgram.methods
+      .revokeDelegation(projectBump, projectId, null)
+      .accounts({
+        baseAccount: projectPDA,
+        authority: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+    const sizeAfter = (await provider.connection.getAccountInfo(projectPDA))
+      .data.length;
+    assert.equal(sizeAfter, sizeBefore);
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.maxMembers, 50);
+
+    const [invalid] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .changeMaxMembersProposal(
+          projectBump,
+          projectId,
+          113,
+          proposalMetadata("Change max members")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: invalid,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "raised the members above what proposals can hold";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidMaxMembers");
+    }
+
+    // The project is full once the cap is lowered to its current members
+    await changeMaxMembers(state.signatories.length);
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [full] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .addNewSignatoryProposal(
+          projectBump,
+          projectId,
+          [extra.publicKey],
+          [],
+          proposalMetadata("Add signatories")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: full,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "added a member above the cap";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "TooManyMembers");
+    }
+
+    await changeMaxMembers(50);
+  });
+
+  it("Tracks votes in a bitmap by member slot", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+
+    const state = await projectProgram.account.projectParameter.fetch(
+      projectPDA
+    );
+    const aliceSlot = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == alice.publicKey.toBase58()
+    );
+    const bobSlot = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == bob.publicKey.toBase58()
+    );
+
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        projectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        projectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+    await projectProgram.methods
+      .rejectProposal(
+        projectBump,
+        projectId,
+        await memberSlot(projectPDA, bob.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: bob.publicKey,
+      })
+      .signers([bob])
+      .rpc();
+
+    let voted = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(voted.voters.length, 14);
+    assert.ok(hasVoted(voted.voters, aliceSlot));
+    assert.ok(!hasVoted(voted.voters, bobSlot));
+    assert.ok(hasVoted(voted.rejectors, bobSlot));
+
+    await projectProgram.methods
+      .revokeVote(
+        projectBump,
+        projectId,
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+
+    voted = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(voted.votes, 0);
+    assert.ok(!hasVoted(voted.voters, aliceSlot));
+
+    await projectProgram.methods
+      .cancelProposal(projectBump, projectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+  });
+
+  it("Votes by slot in a project with over 100 members", async () => {
+    const largeProjectId = uuidv4();
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(largeProjectId.substring(0, 18)),
+          Buffer.from(largeProjectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const [projectPoolPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("pool"),
+        Buffer.from(largeProjectId.substring(0, 18)),
+        Buffer.from(largeProjectId.substring(18, 36)),
+      ],
+      projectProgram.programId
+    );
+
+    await projectProgram.methods
+      .initialize(largeProjectId, percentTransfer)
+      .accounts({
+        baseAccount: projectPDA,
+        projectPoolAccount: projectPoolPDA,
+        tokenMint: USDCMint,
+        authority: alice.publicKey,
+        admin: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([alice])
+      .rpc();
+    await projectProgram.methods
+      .addInitialSignatories(
+        projectBump,
+        largeProjectId,
+        [admin.publicKey, bob.publicKey],
+        threshold,
+        timeLimit
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        authority: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(memberAccounts([admin.publicKey, bob.publicKey]))
+      .signers([alice])
+      .rpc();
+
+    const project = { projectPDA, projectBump, projectId: largeProjectId };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const [maxMembersProposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeMaxMembersProposal(
+        projectBump,
+        largeProjectId,
+        112,
+        proposalMetadata("Change max members")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal: maxMembersProposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await approveAndExecute(
+      project,
+      maxMembersProposal,
+      { changeMaxMembers: {} },
+      [alice, bob]
+    );
+
+    // Members are added ten at a time, the most a single proposal can add
+    const members: anchor.web3.Keypair[] = [];
+    for (let batch = 0; batch < 10; batch++) {
+      const keys = Array.from({ length: 10 }, () =>
+        anchor.web3.Keypair.generate()
+      );
+      state = await projectProgram.account.projectParameter.fetch(projectPDA);
+      const [proposal] = await getProposalPDA(
+        projectPDA,
+        state.proposalCount.toNumber()
+      );
+      await projectProgram.methods
+        .addNewSignatoryProposal(
+          projectBump,
+          largeProjectId,
+          keys.map((key) => key.publicKey),
+          [],
+          proposalMetadata("Add signatories")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await approveAndExecute(
+        project,
+        proposal,
+        { addSignatory: {} },
+        [alice, bob],
+        admin,
+        memberAccounts(keys.map((key) => key.publicKey))
+      );
+      members.push(...keys);
+    }
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.signatories.length, 103);
+
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .changeTimeLimitProposal(
+        projectBump,
+        largeProjectId,
+        newTimeLimit,
+        proposalMetadata("Change time limit")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    // The slot has to be the one the voter holds
+    const last = members[members.length - 1];
+    const lastSlot = await memberSlot(projectPDA, last.publicKey);
+    assert.equal(lastSlot, 102);
+    try {
+      await projectProgram.methods
+        .signProposal(
+          projectBump,
+          largeProjectId,
+          { changeTimeLimit: {} },
+          lastSlot - 1
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal,
+          authority: last.publicKey,
+        })
+        .signers([last])
+        .rpc();
+      throw "voted with the slot of another member";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "InvalidSigner");
+    }
+
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        largeProjectId,
+        { changeTimeLimit: {} },
+        lastSlot
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: last.publicKey,
+      })
+      .signers([last])
+      .rpc();
+
+    // A rejection from one member leaves the proposal open with a hundred more to vote
+    const rejector = members[members.length - 2];
+    await projectProgram.methods
+      .rejectProposal(
+        projectBump,
+        largeProjectId,
+        await memberSlot(projectPDA, rejector.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: rejector.publicKey,
+      })
+      .signers([rejector])
+      .rpc();
+
+    let voted = await projectProgram.account.proposal.fetch(proposal);
+    assert.equal(voted.votingWeight.toNumber(), 103);
+    assert.ok(hasVoted(voted.voters, lastSlot));
+    assert.ok(hasVoted(voted.rejectors, lastSlot - 1));
+    assert.ok(voted.state.active);
+
+    await projectProgram.methods
+      .signProposal(
+        projectBump,
+        largeProjectId,
+        { changeTimeLimit: {} },
+        await memberSlot(projectPDA, alice.publicKey)
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: alice.publicKey,
+      })
+      .signers([alice])
+      .rpc();
+    voted = await projectProgram.account.proposal.fetch(proposal);
+    assert.ok(voted.state.approved);
+
+    await projectProgram.methods
+      .executeProposal(projectBump, largeProjectId)
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.timeLimit, newTimeLimit);
+  });
+
+  it("Replaces a signatory in a single proposal", async () => {
+    const [projectPDA, projectBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("project"),
+          Buffer.from(projectId.substring(0, 18)),
+          Buffer.from(projectId.substring(18, 36)),
+        ],
+        projectProgram.programId
+      );
+    const project = { projectPDA, projectBump, projectId };
+
+    let state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    const danSlot = state.signatories.findIndex(
+      (signatory) => signatory.key.toBase58() == dan.publicKey.toBase58()
+    );
+    const before = state.signatories[danSlot];
+    const members = state.signatories.length;
+    const generation = state.configGeneration.toNumber();
+
+    const [invalid] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    try {
+      await projectProgram.methods
+        .replaceSignatoryProposal(
+          projectBump,
+          projectId,
+          dan.publicKey,
+          alice.publicKey,
+          proposalMetadata("Replace signatory")
+        )
+        .accounts({
+          baseAccount: projectPDA,
+          proposal: invalid,
+          authority: admin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      throw "replaced a signatory with an existing member";
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "SignatoryAlreadyExists");
+    }
+
+    const newDan = anchor.web3.Keypair.generate();
+    const [proposal] = await getProposalPDA(
+      projectPDA,
+      state.proposalCount.toNumber()
+    );
+    await projectProgram.methods
+      .replaceSignatoryProposal(
+        projectBump,
+        projectId,
+        dan.publicKey,
+        newDan.publicKey,
+        proposalMetadata("Replace signatory")
+      )
+      .accounts({
+        baseAccount: projectPDA,
+        proposal,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    await approveAndExecute(
+      project,
+      proposal,
+      { replaceSignatory: {} },
+      [alice, bob],
+      admin,
+      memberAccounts([newDan.publicKey])
+    );
+
+    // The new member takes over the slot with the same roles and weight
+    state = await projectProgram.account.projectParameter.fetch(projectPDA);
+    assert.equal(state.signatories.length, members);
+    assert.equal(
+      state.signatories[danSlot].key.toBase58(),
+      newDan.publicKey.toBase58()
+    );
+    assert.equal(state.signatories[danSlot].permissions, before.permissions);
+    assert.equal(state.signatories[danSlot].weight, before.weight);
+    assert.equal(state.configGeneration.toNumber(), generation + 1);
+
+    dan = newDan;
+  });
 });